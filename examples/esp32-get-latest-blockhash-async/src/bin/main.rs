@@ -21,7 +21,7 @@ use esp_println::println; //
 use esp_wifi::wifi::{self, WifiController, WifiDevice, WifiEvent, WifiState}; //
 use esp_wifi::EspWifiController; //
 use solana_esp_sdk::net::ReqwlessAsyncClient;
-use solana_esp_sdk::rpc::RpcClient; //
+use solana_esp_sdk::rpc::{ContextConfig, RpcClient}; //
 
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
@@ -106,9 +106,9 @@ async fn main(spawner: Spawner) {
     let mut led = Output::new(peripherals.GPIO2, Level::High, OutputConfig::default());
     loop {
         led.toggle();
-        let hash = rpc.get_latest_blockhash().await;
+        let hash = rpc.get_latest_blockhash(ContextConfig::default()).await;
         let hash = match hash {
-            Ok(hash) => hash,
+            Ok(latest) => latest.blockhash,
             Err(e) => {
                 println!("Error: {:?}", e);
                 Timer::after(Duration::from_millis(1000)).await;
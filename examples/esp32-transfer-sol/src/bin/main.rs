@@ -24,7 +24,7 @@ use solana_esp_sdk::hash::Hash;
 //
 use solana_esp_sdk::net::ReqwlessAsyncClient;
 use solana_esp_sdk::prelude::{AccountMeta, Instruction, Transaction};
-use solana_esp_sdk::rpc::RpcClient; //
+use solana_esp_sdk::rpc::{ContextConfig, RpcClient}; //
 
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> ! {
@@ -111,11 +111,11 @@ async fn main(spawner: Spawner) {
     info!("rpc initialized!");
     let recent_hash: Hash;
     loop {
-        let hash = rpc.get_latest_blockhash().await;
+        let hash = rpc.get_latest_blockhash(ContextConfig::default()).await;
         match hash {
-            Ok(hash) => {
-                println!("hash: {}", hash);
-                recent_hash = hash;
+            Ok(latest) => {
+                println!("hash: {}", latest.blockhash);
+                recent_hash = latest.blockhash;
                 break;
             }
             Err(e) => println!("Error: {:?}", e),
@@ -163,16 +163,21 @@ async fn main(spawner: Spawner) {
         Err(e) => println!("Error: {:?}", e),
     }
 
-    // let address = "ALcEQcnFpwij9xBKmUuz8QAyQkwtVDxhhvrogS9VGY3P";
+    // let address: Address = "ALcEQcnFpwij9xBKmUuz8QAyQkwtVDxhhvrogS9VGY3P".parse().unwrap();
 
     // let mut data_buffer = [0; 200];
     // let mut resp_buffer = [0; 2048];
 
-    // let data = rpc
-    //     .get_data(address, &mut data_buffer, &mut resp_buffer)
+    // let account = rpc
+    //     .get_account_info(
+    //         &address,
+    //         AccountInfoConfig::default(),
+    //         &mut data_buffer,
+    //         &mut resp_buffer,
+    //     )
     //     .await;
-    // match data {
-    //     Ok(data) => println!("data: {:?}", data),
+    // match account {
+    //     Ok(account) => println!("account: {:?}", account.value),
     //     Err(e) => println!("Error: {:?}", e),
     // }
 
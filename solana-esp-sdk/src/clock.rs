@@ -0,0 +1,184 @@
+use crate::{
+    rpc::{AsyncClient, Commitment, ContextConfig, RpcClient},
+    types::{Result, SdkError},
+};
+
+/// Shortest local interval over which a drift measurement is trusted. Block
+/// times only have one-second resolution, so each sample can be off by up to
+/// two seconds, about 185 ppm over three hours.
+pub const MIN_DRIFT_INTERVAL_MS: u64 = 3 * 60 * 60 * 1000;
+/// Largest drift sample accepted, in parts per million. Crystals drift by
+/// tens of ppm; anything beyond this is treated as a clock jump.
+pub const MAX_DRIFT_PPM: i64 = 200;
+/// Weight of the running estimate against a new sample; each sample moves
+/// the estimate by `1 / DRIFT_SMOOTHING` of its difference.
+pub const DRIFT_SMOOTHING: i64 = 8;
+
+/// Unix time estimated from block times, for boards without a real-time clock.
+///
+/// Each `sync` anchors the chain time to a reading of the caller's monotonic
+/// millisecond clock. Once `MIN_DRIFT_INTERVAL_MS` has passed since the
+/// sync drift was last measured from, the next sync also measures how fast
+/// the local clock runs; samples are averaged so the one-second resolution
+/// of block times does not turn into a correction.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChainClock {
+    anchor: Option<Anchor>,
+    /// Observation the next drift measurement starts from; kept across
+    /// syncs until enough time has passed to measure against it.
+    drift_ref: Option<Anchor>,
+    /// Smoothed drift in parts per billion, so averaging does not stall on
+    /// integer rounding.
+    drift_ppb: i64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Anchor {
+    unix_ms: i64,
+    local_ms: u64,
+}
+
+impl ChainClock {
+    pub const fn new() -> Self {
+        ChainClock {
+            anchor: None,
+            drift_ref: None,
+            drift_ppb: 0,
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    /// Measured local clock error in parts per million; positive when the
+    /// local clock runs slow.
+    pub fn drift_ppm(&self) -> i64 {
+        self.drift_ppb / 1_000
+    }
+
+    /// Estimated Unix time in milliseconds at local time `now_ms`.
+    pub fn now_unix_ms(&self, now_ms: u64) -> Option<i64> {
+        let anchor = self.anchor?;
+        let elapsed = now_ms.saturating_sub(anchor.local_ms) as i64;
+        Some(anchor.unix_ms + elapsed + elapsed * self.drift_ppb / 1_000_000_000)
+    }
+
+    /// Estimated Unix time in seconds at local time `now_ms`.
+    pub fn now_unix(&self, now_ms: u64) -> Option<i64> {
+        self.now_unix_ms(now_ms).map(|ms| ms.div_euclid(1000))
+    }
+
+    /// Re-anchors the clock on an observed Unix time, updating the drift
+    /// estimate when enough local time has passed since the drift reference.
+    pub fn observe(&mut self, unix_ms: i64, now_ms: u64) {
+        let observed = Anchor {
+            unix_ms,
+            local_ms: now_ms,
+        };
+        match self.drift_ref {
+            Some(reference) => {
+                let elapsed = now_ms.saturating_sub(reference.local_ms) as i64;
+                if elapsed >= MIN_DRIFT_INTERVAL_MS as i64 {
+                    let error = unix_ms - reference.unix_ms - elapsed;
+                    let sample_ppb = error * 1_000_000_000 / elapsed;
+                    if sample_ppb.abs() <= MAX_DRIFT_PPM * 1_000 {
+                        self.drift_ppb += (sample_ppb - self.drift_ppb) / DRIFT_SMOOTHING;
+                    }
+                    // a rejected jump also restarts the measurement
+                    self.drift_ref = Some(observed);
+                }
+            }
+            None => self.drift_ref = Some(observed),
+        }
+        self.anchor = Some(observed);
+    }
+
+    /// Anchors the clock on the block time of the latest confirmed slot.
+    /// `now_ms` is read once the node has answered.
+    pub async fn sync<C: AsyncClient>(
+        &mut self,
+        rpc: &RpcClient<'_, C>,
+        now_ms: impl Fn() -> u64,
+    ) -> Result<i64> {
+        let config = ContextConfig {
+            commitment: Some(Commitment::Confirmed),
+            ..Default::default()
+        };
+        let slot = rpc.get_slot(config).await?;
+        let block_time = rpc.get_block_time(slot).await?.ok_or(SdkError::Rpc)?;
+        self.observe(block_time * 1000, now_ms());
+        Ok(block_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE_MS: u64 = 60 * 1000;
+    const START_MS: i64 = 1_700_000_000_000;
+
+    /// Syncs at `step` ten-minute intervals in, with the local clock
+    /// running `drift_ppm` slow and the block time `jitter_ms` off before it
+    /// is truncated to whole seconds.
+    fn sync_at(clock: &mut ChainClock, step: u64, drift_ppm: i64, jitter_ms: i64) {
+        let chain_ms = (step * 10 * MINUTE_MS) as i64;
+        let local_ms = chain_ms - chain_ms * drift_ppm / 1_000_000;
+        let block_ms = (START_MS + chain_ms + jitter_ms).div_euclid(1000) * 1000;
+        clock.observe(block_ms, local_ms as u64);
+    }
+
+    #[test]
+    fn converges_on_drift_across_frequent_syncs() {
+        let mut clock = ChainClock::new();
+        for step in 0..18 {
+            sync_at(&mut clock, step, 50, 0);
+        }
+        // no sample yet: syncs are closer than the measurement interval
+        assert_eq!(clock.drift_ppm(), 0);
+        for step in 18..72 * 6 {
+            sync_at(&mut clock, step, 50, 0);
+        }
+        assert!(
+            (45..=50).contains(&clock.drift_ppm()),
+            "{}",
+            clock.drift_ppm()
+        );
+    }
+
+    #[test]
+    fn smooths_block_time_jitter() {
+        let mut clock = ChainClock::new();
+        let mut worst = 0;
+        for step in 0..72 * 6 {
+            // one-second errors in either direction, changing between samples
+            let jitter = match (step / 18) % 3 {
+                0 => 999,
+                1 => -1_000,
+                _ => 0,
+            };
+            sync_at(&mut clock, step, 0, jitter);
+            worst = worst.max(clock.drift_ppm().abs());
+        }
+        assert!(worst <= 25, "{worst}");
+    }
+
+    #[test]
+    fn ignores_clock_jumps() {
+        let mut clock = ChainClock::new();
+        let interval = MIN_DRIFT_INTERVAL_MS as i64;
+        clock.observe(0, 0);
+        // a one-minute jump over three hours is far beyond any crystal
+        clock.observe(interval + 60_000, interval as u64);
+        assert_eq!(clock.drift_ppm(), 0);
+        assert_eq!(
+            clock.now_unix_ms(interval as u64 + MINUTE_MS),
+            Some(interval + 60_000 + MINUTE_MS as i64)
+        );
+
+        // measurement restarts from the jumped observation
+        clock.observe(2 * interval + 60_000 + 1_080, 2 * interval as u64);
+        assert_eq!(clock.drift_ppm(), 100 / DRIFT_SMOOTHING);
+    }
+}
@@ -0,0 +1,57 @@
+use crate::hash::Hash;
+
+/// Genesis hash of mainnet-beta, `5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d`.
+pub const MAINNET_GENESIS_HASH: Hash = Hash([
+    69, 41, 105, 152, 166, 248, 226, 167, 132, 219, 93, 159, 149, 225, 143, 194, 63, 112, 68, 26,
+    16, 57, 68, 104, 1, 8, 152, 121, 176, 140, 126, 240,
+]);
+/// Genesis hash of devnet, `EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG`.
+pub const DEVNET_GENESIS_HASH: Hash = Hash([
+    206, 89, 219, 80, 128, 252, 44, 109, 59, 207, 124, 169, 7, 18, 211, 194, 229, 230, 194, 143,
+    39, 240, 223, 187, 153, 83, 189, 176, 137, 76, 3, 171,
+]);
+/// Genesis hash of testnet, `4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY`.
+pub const TESTNET_GENESIS_HASH: Hash = Hash([
+    58, 19, 46, 206, 16, 48, 94, 193, 131, 7, 37, 80, 47, 162, 183, 231, 235, 129, 87, 233, 18, 61,
+    76, 31, 101, 74, 113, 120, 113, 97, 220, 33,
+]);
+
+/// Network an `RpcClient` is meant to talk to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cluster<'a> {
+    Mainnet,
+    Devnet,
+    Testnet,
+    /// A local `solana-test-validator`; its genesis hash changes on every
+    /// reset, so it is never checked.
+    Localnet,
+    Custom {
+        url: &'a str,
+        /// Expected genesis hash, or `None` to skip the check.
+        genesis_hash: Option<Hash>,
+    },
+}
+
+impl<'a> Cluster<'a> {
+    /// Public RPC endpoint, or the custom URL.
+    pub fn url(&self) -> &'a str {
+        match self {
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+            Cluster::Custom { url, .. } => url,
+        }
+    }
+
+    /// Genesis hash the cluster's endpoints must report, if known.
+    pub fn genesis_hash(&self) -> Option<Hash> {
+        match self {
+            Cluster::Mainnet => Some(MAINNET_GENESIS_HASH),
+            Cluster::Devnet => Some(DEVNET_GENESIS_HASH),
+            Cluster::Testnet => Some(TESTNET_GENESIS_HASH),
+            Cluster::Localnet => None,
+            Cluster::Custom { genesis_hash, .. } => *genesis_hash,
+        }
+    }
+}
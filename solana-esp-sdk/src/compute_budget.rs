@@ -0,0 +1,251 @@
+use crate::{
+    crypto::Address,
+    instruction::Instruction,
+    types::{Result, SdkError},
+};
+
+/// `ComputeBudget111111111111111111111111111111`
+pub const COMPUTE_BUDGET_PROGRAM_ID: Address = Address::new([
+    3, 6, 70, 111, 229, 33, 23, 50, 255, 236, 173, 186, 114, 195, 155, 231, 188, 140, 229, 187,
+    197, 247, 18, 107, 44, 67, 155, 58, 64, 0, 0, 0,
+]);
+
+/// Highest compute unit limit a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Most instructions `with_compute_budget` can hold, budget instructions included.
+pub const MAX_INSTRUCTIONS: usize = 16;
+
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Data of a `SetComputeUnitLimit` instruction.
+pub fn set_compute_unit_limit_data(units: u32) -> [u8; 5] {
+    let mut data = [SET_COMPUTE_UNIT_LIMIT, 0, 0, 0, 0];
+    data[1..].copy_from_slice(&units.to_le_bytes());
+    data
+}
+
+/// `consumed` units plus `margin_percent`, capped at `MAX_COMPUTE_UNIT_LIMIT`.
+pub fn compute_unit_limit_with_margin(consumed: u32, margin_percent: u32) -> u32 {
+    let limit = (consumed as u64).saturating_mul(100 + margin_percent as u64) / 100;
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// Data of a `SetComputeUnitPrice` instruction, in micro-lamports per unit.
+pub fn set_compute_unit_price_data(micro_lamports: u64) -> [u8; 9] {
+    let mut data = [SET_COMPUTE_UNIT_PRICE, 0, 0, 0, 0, 0, 0, 0, 0];
+    data[1..].copy_from_slice(&micro_lamports.to_le_bytes());
+    data
+}
+
+/// Most entries `getRecentPrioritizationFees` returns, one per recent slot.
+pub const MAX_PRIORITIZATION_FEES: usize = 150;
+
+/// Lowest priority fee that landed a transaction in `slot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrioritizationFee {
+    pub slot: u64,
+    /// Micro-lamports per compute unit.
+    pub prioritization_fee: u64,
+}
+
+/// Picks a compute unit price from recent prioritization fees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriorityFeeEstimator {
+    /// Percentile of recent fees to pay, 0 to 100.
+    pub percentile: u8,
+    /// Lowest price returned, in micro-lamports per unit.
+    pub floor: u64,
+    /// Highest price returned, in micro-lamports per unit.
+    pub ceiling: u64,
+}
+
+impl Default for PriorityFeeEstimator {
+    fn default() -> Self {
+        PriorityFeeEstimator {
+            percentile: 75,
+            floor: 0,
+            ceiling: 1_000_000,
+        }
+    }
+}
+
+impl PriorityFeeEstimator {
+    /// Nearest-rank percentile of `fees`, clamped to the floor and ceiling.
+    pub fn estimate(&self, fees: &[PrioritizationFee]) -> u64 {
+        let mut sorted: heapless::Vec<u64, MAX_PRIORITIZATION_FEES> = fees
+            .iter()
+            .take(MAX_PRIORITIZATION_FEES)
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+        sorted.sort_unstable();
+        let price = match sorted.len() {
+            0 => 0,
+            len => {
+                let rank = (len * self.percentile.min(100) as usize).div_ceil(100);
+                sorted[rank.saturating_sub(1)]
+            }
+        };
+        price.clamp(self.floor, self.ceiling.max(self.floor))
+    }
+}
+
+/// Compute Budget instruction carrying `data` from one of the builders above.
+pub fn compute_budget_instruction(data: &[u8]) -> Instruction<'static, 'static, 'static, '_> {
+    Instruction {
+        program_id: &COMPUTE_BUDGET_PROGRAM_ID,
+        data,
+        accounts: &[],
+    }
+}
+
+/// Copies `instructions` behind the given budget instructions, dropping any
+/// existing ones of the same kind so the new values take effect.
+pub fn with_compute_budget<'a, 'b, 'c, 'd>(
+    budget: &[Instruction<'a, 'b, 'c, 'd>],
+    instructions: &[Instruction<'a, 'b, 'c, 'd>],
+) -> Result<heapless::Vec<Instruction<'a, 'b, 'c, 'd>, MAX_INSTRUCTIONS>> {
+    let mut out = heapless::Vec::new();
+    for instruction in budget {
+        out.push(instruction.clone())
+            .map_err(|_| SdkError::TransactionTooLarge)?;
+    }
+    for instruction in instructions {
+        let replaced = budget_kind(instruction)
+            .is_some_and(|kind| budget.iter().any(|b| budget_kind(b) == Some(kind)));
+        if !replaced {
+            out.push(instruction.clone())
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+        }
+    }
+    Ok(out)
+}
+
+/// Instruction tag of a Compute Budget instruction.
+fn budget_kind(instruction: &Instruction) -> Option<u8> {
+    match instruction.program_id == &COMPUTE_BUDGET_PROGRAM_ID {
+        true => instruction.data.first().copied(),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(prices: &[u64]) -> heapless::Vec<PrioritizationFee, 8> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(slot, &prioritization_fee)| PrioritizationFee {
+                slot: slot as u64,
+                prioritization_fee,
+            })
+            .collect()
+    }
+
+    fn estimate(percentile: u8, prices: &[u64]) -> u64 {
+        PriorityFeeEstimator {
+            percentile,
+            floor: 0,
+            ceiling: u64::MAX,
+        }
+        .estimate(&fees(prices))
+    }
+
+    #[test]
+    fn adds_margin_up_to_the_maximum_limit() {
+        assert_eq!(compute_unit_limit_with_margin(1_000, 10), 1_100);
+        assert_eq!(compute_unit_limit_with_margin(1_000, 0), 1_000);
+        // rounds down
+        assert_eq!(compute_unit_limit_with_margin(999, 10), 1_098);
+        assert_eq!(compute_unit_limit_with_margin(0, 50), 0);
+        assert_eq!(
+            compute_unit_limit_with_margin(1_300_000, 10),
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+        assert_eq!(
+            compute_unit_limit_with_margin(u32::MAX, u32::MAX),
+            MAX_COMPUTE_UNIT_LIMIT
+        );
+    }
+
+    #[test]
+    fn replaces_budget_instructions_of_the_same_kind() {
+        let old_limit = set_compute_unit_limit_data(200_000);
+        let old_price = set_compute_unit_price_data(1);
+        let new_limit = set_compute_unit_limit_data(50_000);
+        let program = Address::new([9; 32]);
+        let other = Instruction {
+            program_id: &program,
+            data: &[SET_COMPUTE_UNIT_LIMIT],
+            accounts: &[],
+        };
+        let instructions = [
+            compute_budget_instruction(&old_limit),
+            other.clone(),
+            compute_budget_instruction(&old_price),
+        ];
+        let out =
+            with_compute_budget(&[compute_budget_instruction(&new_limit)], &instructions).unwrap();
+        let data: heapless::Vec<&[u8], 4> = out.iter().map(|i| i.data).collect();
+        // the old price stays; only the limit is replaced
+        assert_eq!(data, [&new_limit[..], other.data, &old_price[..]]);
+        assert_eq!(out[1].program_id, &program);
+    }
+
+    #[test]
+    fn rejects_more_than_max_instructions() {
+        let program = Address::new([9; 32]);
+        let instruction = Instruction {
+            program_id: &program,
+            data: &[],
+            accounts: &[],
+        };
+        let price = set_compute_unit_price_data(1);
+        let budget = [compute_budget_instruction(&price)];
+        let fits = alloc::vec![instruction.clone(); MAX_INSTRUCTIONS - 1];
+        assert_eq!(
+            with_compute_budget(&budget, &fits).unwrap().len(),
+            MAX_INSTRUCTIONS
+        );
+        let too_many = alloc::vec![instruction; MAX_INSTRUCTIONS];
+        assert!(matches!(
+            with_compute_budget(&budget, &too_many),
+            Err(SdkError::TransactionTooLarge)
+        ));
+    }
+
+    #[test]
+    fn estimates_nearest_rank_percentiles() {
+        let prices = [40, 10, 30, 20, 50];
+        assert_eq!(estimate(0, &prices), 10);
+        assert_eq!(estimate(50, &prices), 30);
+        assert_eq!(estimate(75, &prices), 40);
+        assert_eq!(estimate(100, &prices), 50);
+        assert_eq!(estimate(200, &prices), 50);
+        assert_eq!(estimate(50, &[10, 20, 30, 40]), 20);
+        assert_eq!(estimate(50, &[7]), 7);
+        assert_eq!(estimate(100, &[7]), 7);
+        assert_eq!(estimate(50, &[]), 0);
+    }
+
+    #[test]
+    fn clamps_to_floor_and_ceiling() {
+        let estimator = PriorityFeeEstimator {
+            percentile: 50,
+            floor: 100,
+            ceiling: 1_000,
+        };
+        assert_eq!(estimator.estimate(&[]), 100);
+        assert_eq!(estimator.estimate(&fees(&[1, 2, 3])), 100);
+        assert_eq!(estimator.estimate(&fees(&[500])), 500);
+        assert_eq!(estimator.estimate(&fees(&[5_000, 9_000])), 1_000);
+        // a ceiling below the floor yields the floor
+        let inverted = PriorityFeeEstimator {
+            ceiling: 10,
+            ..estimator
+        };
+        assert_eq!(inverted.estimate(&fees(&[5_000])), 100);
+    }
+}
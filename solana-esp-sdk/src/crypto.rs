@@ -1,13 +1,20 @@
-use core::{fmt, ops::Deref, str::from_utf8_unchecked};
+use core::{
+    fmt,
+    ops::Deref,
+    str::{from_utf8_unchecked, FromStr},
+};
 
 use ed25519_compact::{KeyPair as Ed25519CompactKeyPair, Noise, PublicKey, Seed, Signature};
 
+#[cfg(feature = "x25519")]
+use crate::types::SdkError;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[repr(transparent)]
 pub struct Address([u8; 32]);
 
 impl Address {
-    pub fn new(address: [u8; 32]) -> Address {
+    pub const fn new(address: [u8; 32]) -> Address {
         Address(address)
     }
 
@@ -45,8 +52,37 @@ impl fmt::Display for Address {
     }
 }
 
+/// Maximum string length of a base58 encoded address.
+pub const MAX_BASE58_LEN: usize = 44;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseAddressError {
+    WrongSize,
+    Invalid,
+}
+
+impl FromStr for Address {
+    type Err = ParseAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use five8::DecodeError;
+        if s.len() > MAX_BASE58_LEN {
+            return Err(ParseAddressError::WrongSize);
+        }
+        let mut bytes = [0; 32];
+        five8::decode_32(s, &mut bytes).map_err(|e| match e {
+            DecodeError::InvalidChar(_) => ParseAddressError::Invalid,
+            DecodeError::TooLong
+            | DecodeError::TooShort
+            | DecodeError::LargestTermTooHigh
+            | DecodeError::OutputTooLong => ParseAddressError::WrongSize,
+        })?;
+        Ok(Address(bytes))
+    }
+}
+
 fn write_as_base58(f: &mut fmt::Formatter, h: &Address) -> fmt::Result {
-    let mut out = [0u8; 44];
+    let mut out = [0u8; MAX_BASE58_LEN];
     let len = five8::encode_32(&h.0, &mut out) as usize;
     // any sequence of base58 chars is valid utf8
     let as_str = unsafe { from_utf8_unchecked(&out[..len]) };
@@ -77,6 +113,60 @@ impl Keypair {
         &self.0.sk
     }
     pub fn sign_message(&self, message: impl AsRef<[u8]>, noise: Option<[u8; 16]>) -> [u8; 64] {
-        *self.0.sk.sign(message, noise.map(|n| Noise::new(n)))
+        *self.0.sk.sign(message, noise.map(Noise::new))
+    }
+}
+
+#[cfg(feature = "x25519")]
+impl Address {
+    /// Converts the ed25519 public key to its X25519 (Montgomery) form.
+    pub fn to_x25519(&self) -> Result<[u8; 32], SdkError> {
+        let pk = ed25519_compact::x25519::PublicKey::from_ed25519(&PublicKey::new(self.0))
+            .map_err(|_| SdkError::Crypto)?;
+        Ok(*pk)
+    }
+}
+
+#[cfg(feature = "x25519")]
+impl Keypair {
+    /// Converts the ed25519 secret key to an X25519 scalar.
+    pub fn x25519_secret_key(&self) -> Result<[u8; 32], SdkError> {
+        let sk = ed25519_compact::x25519::SecretKey::from_ed25519(&self.0.sk)
+            .map_err(|_| SdkError::Crypto)?;
+        Ok(*sk)
+    }
+
+    /// X25519 key agreement with the owner of `peer`. The output is not
+    /// uniform and must be hashed before use as a key.
+    pub fn diffie_hellman(&self, peer: &Address) -> Result<[u8; 32], SdkError> {
+        let sk = ed25519_compact::x25519::SecretKey::from_ed25519(&self.0.sk)
+            .map_err(|_| SdkError::Crypto)?;
+        let pk = ed25519_compact::x25519::PublicKey::new(peer.to_x25519()?);
+        let shared = pk.dh(&sk).map_err(|_| SdkError::Crypto)?;
+        Ok(*shared)
+    }
+}
+
+#[cfg(all(test, feature = "x25519"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_keys_to_x25519() {
+        let alice = Keypair::new_from_seed([1; 32]);
+        let bob = Keypair::new_from_seed([2; 32]);
+        for keypair in [&alice, &bob] {
+            let secret =
+                ed25519_compact::x25519::SecretKey::new(keypair.x25519_secret_key().unwrap());
+            assert_eq!(
+                *secret.recover_public_key().unwrap(),
+                keypair.public_key().to_x25519().unwrap()
+            );
+        }
+        let shared = alice.diffie_hellman(bob.public_key()).unwrap();
+        assert_eq!(shared, bob.diffie_hellman(alice.public_key()).unwrap());
+        assert_ne!(shared, [0; 32]);
+        let carol = Keypair::new_from_seed([3; 32]);
+        assert_ne!(shared, alice.diffie_hellman(carol.public_key()).unwrap());
     }
 }
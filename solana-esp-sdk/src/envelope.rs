@@ -0,0 +1,279 @@
+use base64::Engine;
+use chacha20poly1305::{aead::AeadInPlace, ChaCha20Poly1305, Key, KeyInit, Nonce, Tag};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    crypto::{Address, Keypair},
+    types::{Result, SdkError},
+};
+
+pub const NONCE_LEN: usize = 12;
+pub const TAG_LEN: usize = 16;
+/// Bytes an envelope adds to the plaintext: sender, nonce and tag.
+pub const OVERHEAD: usize = 32 + NONCE_LEN + TAG_LEN;
+
+const KDF_DOMAIN: &[u8] = b"solana-esp-sdk envelope v1";
+
+/// Encrypted payload from one Solana identity to another.
+///
+/// Keys are converted to X25519, the shared secret is hashed together with
+/// both addresses into a ChaCha20-Poly1305 key, and the addresses are bound
+/// as associated data.
+///
+/// Binary layout: `sender address | nonce | ciphertext | tag`
+#[derive(Debug, Clone)]
+pub struct Envelope<'a> {
+    pub sender: Address,
+    pub nonce: [u8; NONCE_LEN],
+    pub ciphertext: &'a [u8],
+    pub tag: [u8; TAG_LEN],
+}
+
+impl<'a> Envelope<'a> {
+    /// Size of an envelope carrying `plaintext_len` bytes.
+    pub const fn encoded_len(plaintext_len: usize) -> usize {
+        plaintext_len + OVERHEAD
+    }
+
+    /// Size of the base64 form of an envelope carrying `plaintext_len` bytes.
+    pub const fn base64_len(plaintext_len: usize) -> usize {
+        Self::encoded_len(plaintext_len).div_ceil(3) * 4
+    }
+
+    /// Encrypts `plaintext` for `recipient` and writes the binary envelope
+    /// into `out`. `nonce` must never repeat for the same sender and recipient.
+    pub fn seal<'buf>(
+        sender: &Keypair,
+        recipient: &Address,
+        nonce: [u8; NONCE_LEN],
+        plaintext: &[u8],
+        out: &'buf mut [u8],
+    ) -> Result<&'buf [u8]> {
+        let len = Self::encoded_len(plaintext.len());
+        if out.len() < len {
+            return Err(SdkError::Serialize);
+        }
+        let sender_address = sender.public_key();
+        let cipher = cipher(sender.diffie_hellman(recipient)?, sender_address, recipient);
+        let aad = associated_data(sender_address, recipient);
+
+        let (header, rest) = out[..len].split_at_mut(32 + NONCE_LEN);
+        header[..32].copy_from_slice(sender_address.as_ref());
+        header[32..].copy_from_slice(&nonce);
+        let (ciphertext, tag) = rest.split_at_mut(plaintext.len());
+        ciphertext.copy_from_slice(plaintext);
+        let computed = cipher
+            .encrypt_in_place_detached(Nonce::from_slice(&nonce), &aad, ciphertext)
+            .map_err(|_| SdkError::Crypto)?;
+        tag.copy_from_slice(&computed);
+        Ok(&out[..len])
+    }
+
+    /// Splits a binary envelope without decrypting it.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < OVERHEAD {
+            return Err(SdkError::Deserialize);
+        }
+        let mut sender = [0u8; 32];
+        sender.copy_from_slice(&bytes[..32]);
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce.copy_from_slice(&bytes[32..32 + NONCE_LEN]);
+        let mut tag = [0u8; TAG_LEN];
+        tag.copy_from_slice(&bytes[bytes.len() - TAG_LEN..]);
+        Ok(Self {
+            sender: Address::new(sender),
+            nonce,
+            ciphertext: &bytes[32 + NONCE_LEN..bytes.len() - TAG_LEN],
+            tag,
+        })
+    }
+
+    /// Decrypts the envelope with the recipient's keypair into `out`.
+    pub fn open<'buf>(&self, recipient: &Keypair, out: &'buf mut [u8]) -> Result<&'buf [u8]> {
+        let plaintext = out
+            .get_mut(..self.ciphertext.len())
+            .ok_or(SdkError::Deserialize)?;
+        let recipient_address = recipient.public_key();
+        let cipher = cipher(
+            recipient.diffie_hellman(&self.sender)?,
+            &self.sender,
+            recipient_address,
+        );
+        let aad = associated_data(&self.sender, recipient_address);
+        plaintext.copy_from_slice(self.ciphertext);
+        if cipher
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&self.nonce),
+                &aad,
+                plaintext,
+                Tag::from_slice(&self.tag),
+            )
+            .is_err()
+        {
+            plaintext.fill(0);
+            return Err(SdkError::Crypto);
+        }
+        Ok(plaintext)
+    }
+}
+
+/// Base64-encodes a binary envelope, e.g. for a memo instruction.
+pub fn to_base64<'buf>(envelope: &[u8], out: &'buf mut [u8]) -> Result<&'buf str> {
+    let len = base64::engine::general_purpose::STANDARD
+        .encode_slice(envelope, out)
+        .map_err(|_| SdkError::Serialize)?;
+    // base64 output is always ASCII
+    core::str::from_utf8(&out[..len]).map_err(|_| SdkError::Serialize)
+}
+
+/// Decodes the base64 form produced by `to_base64`.
+pub fn from_base64<'buf>(encoded: &[u8], out: &'buf mut [u8]) -> Result<&'buf [u8]> {
+    let len = base64::engine::general_purpose::STANDARD
+        .decode_slice(encoded, out)
+        .map_err(|_| SdkError::Deserialize)?;
+    Ok(&out[..len])
+}
+
+fn associated_data(sender: &Address, recipient: &Address) -> [u8; 64] {
+    let mut aad = [0u8; 64];
+    aad[..32].copy_from_slice(sender.as_ref());
+    aad[32..].copy_from_slice(recipient.as_ref());
+    aad
+}
+
+fn cipher(mut shared: [u8; 32], sender: &Address, recipient: &Address) -> ChaCha20Poly1305 {
+    let key = Sha256::new()
+        .chain_update(KDF_DOMAIN)
+        .chain_update(shared)
+        .chain_update(sender.as_ref())
+        .chain_update(recipient.as_ref())
+        .finalize();
+    shared.fill(0);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &[u8] = b"open the gate at 06:00";
+
+    fn parties() -> (Keypair, Keypair) {
+        (
+            Keypair::new_from_seed([1; 32]),
+            Keypair::new_from_seed([2; 32]),
+        )
+    }
+
+    fn sealed(
+        sender: &Keypair,
+        recipient: &Keypair,
+    ) -> [u8; Envelope::encoded_len(PLAINTEXT.len())] {
+        let mut out = [0u8; Envelope::encoded_len(PLAINTEXT.len())];
+        Envelope::seal(
+            sender,
+            recipient.public_key(),
+            [9; NONCE_LEN],
+            PLAINTEXT,
+            &mut out,
+        )
+        .unwrap();
+        out
+    }
+
+    #[test]
+    fn seals_and_opens() {
+        let (sender, recipient) = parties();
+        let bytes = sealed(&sender, &recipient);
+        let envelope = Envelope::parse(&bytes).unwrap();
+        assert_eq!(&envelope.sender, sender.public_key());
+        assert_eq!(envelope.nonce, [9; NONCE_LEN]);
+        assert_ne!(envelope.ciphertext, PLAINTEXT);
+        let mut out = [0u8; 32];
+        assert_eq!(envelope.open(&recipient, &mut out).unwrap(), PLAINTEXT);
+
+        let mut small = [0u8; Envelope::encoded_len(PLAINTEXT.len()) - 1];
+        assert!(Envelope::seal(
+            &sender,
+            recipient.public_key(),
+            [9; NONCE_LEN],
+            PLAINTEXT,
+            &mut small
+        )
+        .is_err());
+        assert!(Envelope::parse(&bytes[..OVERHEAD - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_keys() {
+        let (sender, recipient) = parties();
+        let other = Keypair::new_from_seed([3; 32]);
+        let bytes = sealed(&sender, &recipient);
+        let mut out = [0u8; 32];
+
+        let envelope = Envelope::parse(&bytes).unwrap();
+        assert!(matches!(
+            envelope.open(&other, &mut out),
+            Err(SdkError::Crypto)
+        ));
+        assert_eq!(out, [0; 32]);
+
+        // a claimed sender other than the one who sealed it
+        let mut forged = Envelope::parse(&bytes).unwrap();
+        forged.sender = *other.public_key();
+        assert!(matches!(
+            forged.open(&recipient, &mut out),
+            Err(SdkError::Crypto)
+        ));
+    }
+
+    #[test]
+    fn rejects_tampering() {
+        let (sender, recipient) = parties();
+        let bytes = sealed(&sender, &recipient);
+        let mut out = [0u8; 32];
+        // nonce, ciphertext and tag bytes; the sender is covered above
+        for index in [
+            32,
+            32 + NONCE_LEN,
+            bytes.len() - TAG_LEN - 1,
+            bytes.len() - 1,
+        ] {
+            let mut tampered = bytes;
+            tampered[index] ^= 1;
+            let envelope = Envelope::parse(&tampered).unwrap();
+            assert!(envelope.open(&recipient, &mut out).is_err(), "byte {index}");
+        }
+
+        // the addresses are associated data: the same ciphertext and tag
+        // under a different recipient key fail even with a matching secret
+        let envelope = Envelope::parse(&bytes).unwrap();
+        let shared = recipient.diffie_hellman(&envelope.sender).unwrap();
+        let other = Address::new([5; 32]);
+        let cipher = cipher(shared, &envelope.sender, recipient.public_key());
+        let mut plaintext = [0u8; PLAINTEXT.len()];
+        plaintext.copy_from_slice(envelope.ciphertext);
+        assert!(cipher
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&envelope.nonce),
+                &associated_data(&envelope.sender, &other),
+                &mut plaintext,
+                Tag::from_slice(&envelope.tag),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        let (sender, recipient) = parties();
+        let bytes = sealed(&sender, &recipient);
+        let mut encoded = [0u8; Envelope::base64_len(PLAINTEXT.len())];
+        let text = to_base64(&bytes, &mut encoded).unwrap();
+        assert_eq!(text.len(), Envelope::base64_len(PLAINTEXT.len()));
+        let mut decoded = [0u8; Envelope::encoded_len(PLAINTEXT.len())];
+        assert_eq!(from_base64(text.as_bytes(), &mut decoded).unwrap(), bytes);
+
+        assert!(to_base64(&bytes, &mut [0u8; 8]).is_err());
+        assert!(from_base64(b"not base64!", &mut decoded).is_err());
+    }
+}
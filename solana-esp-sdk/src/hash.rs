@@ -8,7 +8,7 @@ pub const HASH_BYTES: usize = 32;
 /// Maximum string length of a base58 encoded hash.
 pub const MAX_BASE58_LEN: usize = 44;
 
-#[derive(Default)]
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub struct Hash(pub(crate) [u8; HASH_BYTES]);
 
 impl From<[u8; HASH_BYTES]> for Hash {
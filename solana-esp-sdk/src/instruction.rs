@@ -1,5 +1,20 @@
 use crate::crypto::Address;
 
+/// `11111111111111111111111111111111`
+pub const SYSTEM_PROGRAM_ID: Address = Address::new([0; 32]);
+
+/// `TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA`
+pub const TOKEN_PROGRAM_ID: Address = Address::new([
+    6, 221, 246, 225, 215, 101, 161, 147, 217, 203, 225, 70, 206, 235, 121, 172, 28, 180, 133, 237,
+    95, 91, 55, 145, 58, 140, 245, 133, 126, 255, 0, 169,
+]);
+
+/// `TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb`
+pub const TOKEN_2022_PROGRAM_ID: Address = Address::new([
+    6, 221, 246, 225, 238, 117, 143, 222, 24, 66, 93, 188, 228, 108, 205, 218, 182, 26, 252, 77,
+    131, 185, 13, 39, 254, 189, 249, 40, 216, 161, 139, 252,
+]);
+
 #[derive(Debug, Clone)]
 pub struct AccountMeta<'a> {
     pub address: &'a Address,
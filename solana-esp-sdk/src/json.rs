@@ -0,0 +1,1006 @@
+//! Allocation-free JSON reader over `&[u8]`.
+//!
+//! `Value::parse` validates a document in a single pass; afterwards every
+//! `Value` is a well-formed sub-slice of the input and navigation (`get`,
+//! `path`, `members`, `elements`) just re-scans the bytes it needs, without
+//! building a tree.
+
+use core::str::from_utf8;
+
+use crate::{
+    crypto::Address,
+    hash::Hash,
+    signature::Signature,
+    types::{Result, SdkError},
+};
+
+/// Maximum nesting of objects and arrays.
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+}
+
+/// A JSON value borrowed from the response body.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Value<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> Value<'a> {
+    pub const NULL: Value<'static> = Value { raw: b"null" };
+
+    /// Validates `input` as a single JSON document.
+    pub fn parse(input: &'a [u8]) -> Result<Self> {
+        let start = skip_ws(input, 0);
+        let end = scan_value(input, start).ok_or(SdkError::ResponseParseError)?;
+        if skip_ws(input, end) != input.len() {
+            return Err(SdkError::ResponseParseError);
+        }
+        Ok(Value {
+            raw: &input[start..end],
+        })
+    }
+
+    /// The exact bytes of this value in the input.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    pub fn kind(&self) -> Kind {
+        match self.raw[0] {
+            b'{' => Kind::Object,
+            b'[' => Kind::Array,
+            b'"' => Kind::String,
+            b't' | b'f' => Kind::Bool,
+            b'n' => Kind::Null,
+            _ => Kind::Number,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.kind() == Kind::Null
+    }
+
+    /// Looks up `key` in an object. Returns `None` for other kinds.
+    pub fn get(&self, key: &str) -> Option<Value<'a>> {
+        self.members()
+            .find(|(k, _)| k.eq_str(key))
+            .map(|(_, value)| value)
+    }
+
+    /// Follows a sequence of object keys, e.g. `&["result", "context", "slot"]`.
+    pub fn path(&self, keys: &[&str]) -> Option<Value<'a>> {
+        keys.iter().try_fold(*self, |value, key| value.get(key))
+    }
+
+    /// Element `index` of an array.
+    pub fn at(&self, index: usize) -> Option<Value<'a>> {
+        self.elements().nth(index)
+    }
+
+    /// Decodes `key` as `T`. A missing key decodes like `null`, so
+    /// `Option<T>` fields may be absent.
+    pub fn field<T: Decode<'a>>(&self, key: &str) -> Result<T> {
+        T::decode(self.get(key).unwrap_or(Value::NULL))
+    }
+
+    pub fn decode<T: Decode<'a>>(self) -> Result<T> {
+        T::decode(self)
+    }
+
+    /// Key/value pairs of an object, in document order. Empty for other kinds.
+    pub fn members(&self) -> Members<'a> {
+        Members {
+            raw: self.raw,
+            pos: if self.kind() == Kind::Object {
+                1
+            } else {
+                self.raw.len()
+            },
+        }
+    }
+
+    /// Elements of an array, in document order. Empty for other kinds.
+    pub fn elements(&self) -> Elements<'a> {
+        Elements {
+            raw: self.raw,
+            pos: if self.kind() == Kind::Array {
+                1
+            } else {
+                self.raw.len()
+            },
+        }
+    }
+
+    pub fn as_str(&self) -> Option<JsonStr<'a>> {
+        match self.kind() {
+            Kind::String => Some(JsonStr {
+                raw: &self.raw[1..self.raw.len() - 1],
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.raw {
+            b"true" => Some(true),
+            b"false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.number_str()?.parse().ok()
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.number_str()?.parse().ok()
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.number_str()?.parse().ok()
+    }
+
+    fn number_str(&self) -> Option<&'a str> {
+        match self.kind() {
+            // numbers are ASCII
+            Kind::Number => from_utf8(self.raw).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Debug for Value<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match from_utf8(self.raw) {
+            Ok(s) => f.write_str(s),
+            Err(_) => write!(f, "{:?}", self.raw),
+        }
+    }
+}
+
+/// Iterator over the members of an object.
+#[derive(Clone)]
+pub struct Members<'a> {
+    raw: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Members<'a> {
+    type Item = (JsonStr<'a>, Value<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let raw = self.raw;
+        let mut pos = skip_ws(raw, self.pos);
+        match raw.get(pos)? {
+            b',' => pos = skip_ws(raw, pos + 1),
+            b'}' => {
+                self.pos = raw.len();
+                return None;
+            }
+            _ => {}
+        }
+        let key_end = scan_string(raw, pos)?;
+        let key = JsonStr {
+            raw: &raw[pos + 1..key_end - 1],
+        };
+        // skip ':'
+        let value_start = skip_ws(raw, skip_ws(raw, key_end) + 1);
+        let value_end = scan_value(raw, value_start)?;
+        self.pos = value_end;
+        Some((
+            key,
+            Value {
+                raw: &raw[value_start..value_end],
+            },
+        ))
+    }
+}
+
+/// Iterator over the elements of an array.
+#[derive(Clone)]
+pub struct Elements<'a> {
+    raw: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = Value<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let raw = self.raw;
+        let mut pos = skip_ws(raw, self.pos);
+        match raw.get(pos)? {
+            b',' => pos = skip_ws(raw, pos + 1),
+            b']' => {
+                self.pos = raw.len();
+                return None;
+            }
+            _ => {}
+        }
+        let end = scan_value(raw, pos)?;
+        self.pos = end;
+        Some(Value {
+            raw: &raw[pos..end],
+        })
+    }
+}
+
+/// Contents of a JSON string with escapes still in place.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct JsonStr<'a> {
+    raw: &'a [u8],
+}
+
+impl<'a> JsonStr<'a> {
+    /// Bytes between the quotes, escapes not decoded. Base58 and base64
+    /// payloads never contain escapes, so this is what decoders want.
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Borrows the string when it contains no escapes.
+    pub fn as_str(&self) -> Option<&'a str> {
+        if self.raw.contains(&b'\\') {
+            return None;
+        }
+        from_utf8(self.raw).ok()
+    }
+
+    /// Decoded bytes, escapes resolved.
+    pub fn bytes(&self) -> Unescape<'a> {
+        Unescape {
+            raw: self.raw,
+            pos: 0,
+            pending: [0; 4],
+            pending_len: 0,
+            pending_pos: 0,
+        }
+    }
+
+    /// Compares the decoded string with `s`.
+    pub fn eq_str(&self, s: &str) -> bool {
+        match self.as_str() {
+            Some(plain) => plain == s,
+            None => self.bytes().eq(s.bytes()),
+        }
+    }
+
+    /// Decodes into `buffer`, truncating at a character boundary if it
+    /// does not fit. Returns the decoded string and whether it was truncated.
+    pub fn unescape_into<'buf>(&self, buffer: &'buf mut [u8]) -> (&'buf str, bool) {
+        let mut len = 0;
+        let mut truncated = false;
+        for byte in self.bytes() {
+            if len == buffer.len() {
+                truncated = true;
+                break;
+            }
+            buffer[len] = byte;
+            len += 1;
+        }
+        match from_utf8(&buffer[..len]) {
+            Ok(s) => (s, truncated),
+            Err(e) => {
+                let valid = e.valid_up_to();
+                // SAFETY: from_utf8 validated the first `valid` bytes
+                let s = unsafe { core::str::from_utf8_unchecked(&buffer[..valid]) };
+                (s, true)
+            }
+        }
+    }
+
+    /// Decodes into a heapless string, truncating if it does not fit.
+    pub fn to_heapless<const N: usize>(&self) -> heapless::String<N> {
+        let mut buffer = [0u8; N];
+        let (s, _) = self.unescape_into(&mut buffer);
+        let mut out = heapless::String::new();
+        // s.len() <= N
+        let _ = out.push_str(s);
+        out
+    }
+}
+
+impl core::fmt::Debug for JsonStr<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match from_utf8(self.raw) {
+            Ok(s) => write!(f, "\"{}\"", s),
+            Err(_) => write!(f, "{:?}", self.raw),
+        }
+    }
+}
+
+/// Iterator over the UTF-8 bytes of a JSON string with escapes resolved.
+pub struct Unescape<'a> {
+    raw: &'a [u8],
+    pos: usize,
+    pending: [u8; 4],
+    pending_len: usize,
+    pending_pos: usize,
+}
+
+impl Unescape<'_> {
+    fn hex4(&self, at: usize) -> Option<u32> {
+        let digits = from_utf8(self.raw.get(at..at + 4)?).ok()?;
+        u32::from_str_radix(digits, 16).ok()
+    }
+}
+
+impl Iterator for Unescape<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.pending_pos < self.pending_len {
+            self.pending_pos += 1;
+            return Some(self.pending[self.pending_pos - 1]);
+        }
+        let byte = *self.raw.get(self.pos)?;
+        if byte != b'\\' {
+            self.pos += 1;
+            return Some(byte);
+        }
+        let escaped = *self.raw.get(self.pos + 1)?;
+        self.pos += 2;
+        let c = match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let mut code = self.hex4(self.pos)?;
+                self.pos += 4;
+                if (0xd800..0xdc00).contains(&code)
+                    && self.raw.get(self.pos..self.pos + 2) == Some(b"\\u")
+                {
+                    if let Some(low @ 0xdc00..=0xdfff) = self.hex4(self.pos + 2) {
+                        code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        self.pos += 6;
+                    }
+                }
+                char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            _ => return None,
+        };
+        let len = c.encode_utf8(&mut self.pending).len();
+        self.pending_len = len;
+        self.pending_pos = 1;
+        Some(self.pending[0])
+    }
+}
+
+/// Conversion from a JSON value into a typed result.
+pub trait Decode<'a>: Sized {
+    fn decode(value: Value<'a>) -> Result<Self>;
+}
+
+impl<'a> Decode<'a> for Value<'a> {
+    fn decode(value: Value<'a>) -> Result<Self> {
+        Ok(value)
+    }
+}
+
+impl<'a, T: Decode<'a>> Decode<'a> for Option<T> {
+    fn decode(value: Value<'a>) -> Result<Self> {
+        match value.is_null() {
+            true => Ok(None),
+            false => T::decode(value).map(Some),
+        }
+    }
+}
+
+impl<'a> Decode<'a> for JsonStr<'a> {
+    fn decode(value: Value<'a>) -> Result<Self> {
+        value.as_str().ok_or(SdkError::ResponseParseError)
+    }
+}
+
+impl<'a> Decode<'a> for &'a str {
+    fn decode(value: Value<'a>) -> Result<Self> {
+        value
+            .as_str()
+            .and_then(|s| s.as_str())
+            .ok_or(SdkError::ResponseParseError)
+    }
+}
+
+impl Decode<'_> for bool {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        value.as_bool().ok_or(SdkError::ResponseParseError)
+    }
+}
+
+macro_rules! decode_number {
+    ($($ty:ty),*) => {
+        $(
+            impl Decode<'_> for $ty {
+                fn decode(value: Value<'_>) -> Result<Self> {
+                    value
+                        .number_str()
+                        .and_then(|s| s.parse().ok())
+                        .ok_or(SdkError::ResponseParseError)
+                }
+            }
+        )*
+    };
+}
+
+decode_number!(u8, u16, u32, u64, i32, i64, f64);
+
+impl Decode<'_> for Hash {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let mut bytes = [0u8; 32];
+        five8::decode_32(JsonStr::decode(value)?.raw(), &mut bytes)
+            .map_err(|_| SdkError::ResponseParseError)?;
+        Ok(Hash::from(bytes))
+    }
+}
+
+impl Decode<'_> for Address {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let mut bytes = [0u8; 32];
+        five8::decode_32(JsonStr::decode(value)?.raw(), &mut bytes)
+            .map_err(|_| SdkError::ResponseParseError)?;
+        Ok(Address::new(bytes))
+    }
+}
+
+impl Decode<'_> for Signature {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let mut bytes = [0u8; 64];
+        five8::decode_64(JsonStr::decode(value)?.raw(), &mut bytes)
+            .map_err(|_| SdkError::ResponseParseError)?;
+        Ok(Signature::from(bytes))
+    }
+}
+
+/// Splits a JSON-RPC response arriving in chunks into the elements of its
+/// result array, so results larger than RAM can be decoded one at a time.
+///
+/// The target array is `result` itself, or `result.value` for responses with
+/// context, unless another path is given with `with_path`. Everything outside
+/// it is kept in the skeleton buffer, which can be parsed as an `RpcResponse`
+/// (with an empty array) once the body has ended.
+pub struct ArrayStream<'s, 'e> {
+    skeleton: &'s mut [u8],
+    skeleton_len: usize,
+    element: &'e mut [u8],
+    element_len: usize,
+    depth: usize,
+    in_string: bool,
+    escape: bool,
+    path: Option<&'static [&'static str]>,
+    target: Target,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Searching,
+    /// Inside the array, between elements or in one of the given kind.
+    Array(Option<ElementKind>),
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ElementKind {
+    /// Object, array or string, ended by its closing byte at this depth.
+    Nested(usize),
+    /// Number or literal, ended by the next separator.
+    Scalar,
+}
+
+impl<'s, 'e> ArrayStream<'s, 'e> {
+    pub fn new(skeleton: &'s mut [u8], element: &'e mut [u8]) -> Self {
+        ArrayStream {
+            skeleton,
+            skeleton_len: 0,
+            element,
+            element_len: 0,
+            depth: 0,
+            in_string: false,
+            escape: false,
+            path: None,
+            target: Target::Searching,
+        }
+    }
+
+    /// Streams the array at `path` from the top of the body instead, e.g.
+    /// `&["result", "value", "logs"]`. The last key is matched at the depth
+    /// the path implies. An empty `element` buffer skips the elements.
+    pub fn with_path(
+        skeleton: &'s mut [u8],
+        element: &'e mut [u8],
+        path: &'static [&'static str],
+    ) -> Self {
+        ArrayStream {
+            path: Some(path),
+            ..Self::new(skeleton, element)
+        }
+    }
+
+    /// Consumes the next chunk of the body, passing each complete element to
+    /// `on_element`. Fails with `ResponseParseError` if an element or the
+    /// skeleton does not fit its buffer.
+    pub fn feed(
+        &mut self,
+        chunk: &[u8],
+        mut on_element: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        for &byte in chunk {
+            if let Target::Array(Some(kind)) = self.target {
+                if kind == ElementKind::Scalar
+                    && matches!(byte, b',' | b']' | b' ' | b'\t' | b'\n' | b'\r')
+                {
+                    self.end_element(&mut on_element)?;
+                } else {
+                    self.push_element(byte)?;
+                    if self.element_byte(byte, kind) {
+                        self.end_element(&mut on_element)?;
+                    }
+                    continue;
+                }
+            }
+            if self.target == Target::Array(None) {
+                match byte {
+                    b' ' | b'\t' | b'\n' | b'\r' | b',' => {}
+                    b']' => {
+                        self.push_skeleton(byte)?;
+                        self.depth -= 1;
+                        self.target = Target::Done;
+                    }
+                    _ => {
+                        let kind = match byte {
+                            b'{' | b'[' | b'"' => ElementKind::Nested(0),
+                            _ => ElementKind::Scalar,
+                        };
+                        self.element_len = 0;
+                        self.push_element(byte)?;
+                        self.target = Target::Array(Some(kind));
+                        if let ElementKind::Nested(_) = kind {
+                            self.element_byte(byte, kind);
+                        }
+                    }
+                }
+                continue;
+            }
+            self.push_skeleton(byte)?;
+            if self.in_string {
+                self.string_byte(byte);
+                continue;
+            }
+            match byte {
+                b'"' => self.in_string = true,
+                b'{' => self.depth += 1,
+                b'[' => {
+                    let key = key_before(&self.skeleton[..self.skeleton_len - 1]);
+                    let at_target = match self.path {
+                        Some(path) => {
+                            self.depth == path.len() && key == path.last().map(|key| key.as_bytes())
+                        }
+                        None => matches!(
+                            (self.depth, key),
+                            (1, Some(b"result")) | (2, Some(b"value"))
+                        ),
+                    };
+                    if self.target == Target::Searching && at_target {
+                        self.target = Target::Array(None);
+                    }
+                    self.depth += 1;
+                }
+                b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Everything received outside the target array.
+    pub fn skeleton(&self) -> &[u8] {
+        &self.skeleton[..self.skeleton_len]
+    }
+
+    /// Consumes the stream, returning the skeleton with the buffer's lifetime.
+    pub fn into_skeleton(self) -> &'s [u8] {
+        let skeleton: &'s [u8] = self.skeleton;
+        &skeleton[..self.skeleton_len]
+    }
+
+    /// Whether the target array was found and closed.
+    pub fn is_complete(&self) -> bool {
+        self.target == Target::Done
+    }
+
+    fn push_skeleton(&mut self, byte: u8) -> Result<()> {
+        let slot = self
+            .skeleton
+            .get_mut(self.skeleton_len)
+            .ok_or(SdkError::ResponseParseError)?;
+        *slot = byte;
+        self.skeleton_len += 1;
+        Ok(())
+    }
+
+    fn push_element(&mut self, byte: u8) -> Result<()> {
+        if self.element.is_empty() {
+            return Ok(());
+        }
+        let slot = self
+            .element
+            .get_mut(self.element_len)
+            .ok_or(SdkError::ResponseParseError)?;
+        *slot = byte;
+        self.element_len += 1;
+        Ok(())
+    }
+
+    /// Tracks nesting inside a structured element; true once it is closed.
+    fn element_byte(&mut self, byte: u8, kind: ElementKind) -> bool {
+        let ElementKind::Nested(depth) = kind else {
+            return false;
+        };
+        if self.in_string {
+            self.string_byte(byte);
+            return depth == 0 && !self.in_string;
+        }
+        let depth = match byte {
+            b'"' => {
+                self.in_string = true;
+                depth
+            }
+            b'{' | b'[' => depth + 1,
+            b'}' | b']' => depth - 1,
+            _ => depth,
+        };
+        self.target = Target::Array(Some(ElementKind::Nested(depth)));
+        depth == 0 && matches!(byte, b'}' | b']')
+    }
+
+    fn string_byte(&mut self, byte: u8) {
+        if self.escape {
+            self.escape = false;
+        } else if byte == b'\\' {
+            self.escape = true;
+        } else if byte == b'"' {
+            self.in_string = false;
+        }
+    }
+
+    fn end_element(&mut self, on_element: &mut impl FnMut(&[u8]) -> Result<()>) -> Result<()> {
+        self.target = Target::Array(None);
+        if self.element.is_empty() {
+            return Ok(());
+        }
+        on_element(&self.element[..self.element_len])
+    }
+}
+
+/// Object key directly preceding `bytes`, e.g. `result` in `{"result":`.
+fn key_before(bytes: &[u8]) -> Option<&[u8]> {
+    let bytes = bytes.trim_ascii_end().strip_suffix(b":")?;
+    let bytes = bytes.trim_ascii_end().strip_suffix(b"\"")?;
+    let start = bytes.iter().rposition(|&b| b == b'"')?;
+    Some(&bytes[start + 1..])
+}
+
+fn skip_ws(input: &[u8], mut pos: usize) -> usize {
+    while let Some(b' ' | b'\t' | b'\n' | b'\r') = input.get(pos) {
+        pos += 1;
+    }
+    pos
+}
+
+/// Scans the string starting at `pos` (on the opening quote) and returns the
+/// position after the closing quote.
+fn scan_string(input: &[u8], pos: usize) -> Option<usize> {
+    if input.get(pos) != Some(&b'"') {
+        return None;
+    }
+    let mut pos = pos + 1;
+    loop {
+        match *input.get(pos)? {
+            b'"' => return Some(pos + 1),
+            b'\\' => match *input.get(pos + 1)? {
+                b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => pos += 2,
+                b'u' => {
+                    let hex = input.get(pos + 2..pos + 6)?;
+                    if !hex.iter().all(u8::is_ascii_hexdigit) {
+                        return None;
+                    }
+                    pos += 6;
+                }
+                _ => return None,
+            },
+            0x00..=0x1f => return None,
+            _ => pos += 1,
+        }
+    }
+}
+
+fn scan_number(input: &[u8], pos: usize) -> Option<usize> {
+    let digits = |mut pos: usize| {
+        let start = pos;
+        while input.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        (pos > start).then_some(pos)
+    };
+    let mut pos = pos;
+    if input.get(pos) == Some(&b'-') {
+        pos += 1;
+    }
+    pos = match input.get(pos)? {
+        b'0' => pos + 1,
+        _ => digits(pos)?,
+    };
+    if input.get(pos) == Some(&b'.') {
+        pos = digits(pos + 1)?;
+    }
+    if let Some(b'e' | b'E') = input.get(pos) {
+        pos += 1;
+        if let Some(b'+' | b'-') = input.get(pos) {
+            pos += 1;
+        }
+        pos = digits(pos)?;
+    }
+    Some(pos)
+}
+
+/// Scans the value starting at `pos` and returns the position after it.
+/// Containers are tracked with a bit stack (1 = object) instead of recursion.
+fn scan_value(input: &[u8], pos: usize) -> Option<usize> {
+    let mut stack: u64 = 0;
+    let mut depth = 0;
+    let mut pos = pos;
+
+    loop {
+        // expecting a value
+        pos = skip_ws(input, pos);
+        match *input.get(pos)? {
+            open @ (b'{' | b'[') => {
+                if depth == MAX_DEPTH {
+                    return None;
+                }
+                stack = (stack << 1) | (open == b'{') as u64;
+                depth += 1;
+                pos = skip_ws(input, pos + 1);
+                let close = if open == b'{' { b'}' } else { b']' };
+                if input.get(pos) == Some(&close) {
+                    pos += 1;
+                    stack >>= 1;
+                    depth -= 1;
+                } else {
+                    if open == b'{' {
+                        pos = scan_key(input, pos)?;
+                    }
+                    continue;
+                }
+            }
+            b'"' => pos = scan_string(input, pos)?,
+            b't' if input[pos..].starts_with(b"true") => pos += 4,
+            b'f' if input[pos..].starts_with(b"false") => pos += 5,
+            b'n' if input[pos..].starts_with(b"null") => pos += 4,
+            b'-' | b'0'..=b'9' => pos = scan_number(input, pos)?,
+            _ => return None,
+        }
+
+        // after a value: close containers or move to the next element
+        loop {
+            if depth == 0 {
+                return Some(pos);
+            }
+            pos = skip_ws(input, pos);
+            let in_object = stack & 1 == 1;
+            match (*input.get(pos)?, in_object) {
+                (b',', true) => {
+                    pos = scan_key(input, skip_ws(input, pos + 1))?;
+                    break;
+                }
+                (b',', false) => {
+                    pos += 1;
+                    break;
+                }
+                (b'}', true) | (b']', false) => {
+                    pos += 1;
+                    stack >>= 1;
+                    depth -= 1;
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Scans `"key" :` and returns the position after the colon.
+fn scan_key(input: &[u8], pos: usize) -> Option<usize> {
+    let pos = skip_ws(input, scan_string(input, pos)?);
+    (input.get(pos) == Some(&b':')).then_some(pos + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{string::String, vec::Vec};
+
+    fn unescaped(value: Value) -> String {
+        let bytes: Vec<u8> = value.as_str().unwrap().bytes().collect();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn strings_hide_structural_bytes() {
+        let doc = br#"{"a":"quote \" and ] } [ {","b":[1,"]",{"c":"\\"}],"d":true}"#;
+        let value = Value::parse(doc).unwrap();
+        assert_eq!(unescaped(value.get("a").unwrap()), r#"quote " and ] } [ {"#);
+        assert_eq!(value.members().count(), 3);
+        let b = value.get("b").unwrap();
+        assert_eq!(b.elements().count(), 3);
+        assert_eq!(b.at(1).unwrap().raw(), br#""]""#);
+        assert_eq!(unescaped(b.at(2).unwrap().get("c").unwrap()), "\\");
+        assert_eq!(value.get("d").unwrap().as_bool(), Some(true));
+    }
+
+    #[test]
+    fn unescapes_strings() {
+        let doc = br#"["\u0041\u00e9\u20AC", "\ud83d\ude00!", "\ud83d", "\/\b\f\n\r\t"]"#;
+        let value = Value::parse(doc).unwrap();
+        assert_eq!(unescaped(value.at(0).unwrap()), "Aé€");
+        assert_eq!(unescaped(value.at(1).unwrap()), "😀!");
+        // a lone high surrogate is not a character
+        assert_eq!(unescaped(value.at(2).unwrap()), "\u{fffd}");
+        assert_eq!(unescaped(value.at(3).unwrap()), "/\u{8}\u{c}\n\r\t");
+
+        let s = value.at(1).unwrap().as_str().unwrap();
+        assert_eq!(s.as_str(), None);
+        assert!(s.eq_str("😀!"));
+        let mut buffer = [0u8; 2];
+        assert_eq!(s.unescape_into(&mut buffer), ("", true));
+        assert_eq!(s.to_heapless::<8>().as_str(), "😀!");
+
+        for bad in [&br#""\u12""#[..], br#""\x""#, b"\"tab\there\""] {
+            assert!(Value::parse(bad).is_err());
+        }
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let nested = |depth: usize| {
+            let mut doc = Vec::new();
+            doc.resize(depth, b'[');
+            doc.resize(depth * 2, b']');
+            doc
+        };
+        assert!(Value::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Value::parse(&nested(MAX_DEPTH + 1)).is_err());
+
+        let mut objects = Vec::new();
+        for _ in 0..=MAX_DEPTH {
+            objects.extend_from_slice(br#"{"a":"#);
+        }
+        objects.push(b'1');
+        objects.resize(objects.len() + MAX_DEPTH + 1, b'}');
+        assert!(Value::parse(&objects).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let doc = br#" {"a": [1, 2.5e3, -0, null], "b": {}} "#;
+        assert!(Value::parse(doc).is_ok());
+        for end in 0..doc.len() - 1 {
+            assert!(Value::parse(&doc[..end]).is_err(), "truncated at {end}");
+        }
+        for bad in [
+            &br#"{"a":1} x"#[..],
+            br#"{"a":1}}"#,
+            br#"{"a":1}{}"#,
+            b"[1,]",
+            b"[1 2]",
+            br#"{"a"}"#,
+            br#"{"a":1,}"#,
+            b"01",
+            b"1.",
+            b"tru",
+            b"",
+        ] {
+            assert!(Value::parse(bad).is_err(), "{:?}", from_utf8(bad));
+        }
+    }
+
+    #[test]
+    fn looks_up_members_in_any_order() {
+        let compact = br#"{"context":{"slot":5},"value":{"lamports":7,"owner":"x"}}"#;
+        let spaced = b"{ \"value\" :\n\t{ \"owner\" : \"x\" , \"lamports\" : 7 } ,\r\n \"context\" : { \"slot\" : 5 } }";
+        for doc in [&compact[..], &spaced[..]] {
+            let value = Value::parse(doc).unwrap();
+            assert_eq!(value.path(&["context", "slot"]).unwrap().as_u64(), Some(5));
+            assert_eq!(value.field::<u64>("missing").ok(), None);
+            assert_eq!(value.field::<Option<u64>>("missing").unwrap(), None);
+            let inner = value.get("value").unwrap();
+            assert_eq!(inner.field::<u64>("lamports").unwrap(), 7);
+            assert_eq!(inner.field::<&str>("owner").unwrap(), "x");
+            assert_eq!(inner.get("slot"), None);
+        }
+        // keys with escapes compare decoded
+        let value = Value::parse(br#"{"\u006bey":1}"#).unwrap();
+        assert_eq!(value.get("key").unwrap().as_u64(), Some(1));
+    }
+
+    fn stream(body: &[u8], chunk: usize) -> (Vec<Vec<u8>>, Vec<u8>) {
+        let mut skeleton = [0u8; 128];
+        let mut element = [0u8; 64];
+        let mut stream = ArrayStream::new(&mut skeleton, &mut element);
+        let mut elements = Vec::new();
+        for part in body.chunks(chunk) {
+            stream
+                .feed(part, |element| {
+                    elements.push(element.to_vec());
+                    Ok(())
+                })
+                .unwrap();
+        }
+        assert!(stream.is_complete());
+        (elements, stream.skeleton().to_vec())
+    }
+
+    #[test]
+    fn streams_result_array() {
+        let body =
+            br#"{"jsonrpc":"2.0","result":[{"a":"]\"}"},[1,[2]], "s\\",7 ,null,-1.5e2],"id":1}"#;
+        let expected: [&[u8]; 6] = [
+            br#"{"a":"]\"}"}"#,
+            b"[1,[2]]",
+            br#""s\\""#,
+            b"7",
+            b"null",
+            b"-1.5e2",
+        ];
+        for chunk in [1, 2, 3, 7, body.len()] {
+            let (elements, skeleton) = stream(body, chunk);
+            assert_eq!(elements, expected, "chunk {chunk}");
+            assert_eq!(skeleton, br#"{"jsonrpc":"2.0","result":[],"id":1}"#);
+        }
+    }
+
+    #[test]
+    fn streams_context_value_array() {
+        let body = br#"{"jsonrpc":"2.0","result":{"context":{"slot":9,"note":"[\"value\":["},"value" : [ {"pubkey":"x","account":{"data":["AA==","base64"]}} , {"pubkey":"y"} ]},"id":1}"#;
+        for chunk in [1, 2, 5, body.len()] {
+            let (elements, skeleton) = stream(body, chunk);
+            assert_eq!(
+                elements,
+                [
+                    &br#"{"pubkey":"x","account":{"data":["AA==","base64"]}}"#[..],
+                    br#"{"pubkey":"y"}"#
+                ],
+                "chunk {chunk}"
+            );
+            let skeleton = Value::parse(&skeleton).unwrap();
+            assert_eq!(
+                skeleton
+                    .path(&["result", "context", "slot"])
+                    .unwrap()
+                    .as_u64(),
+                Some(9)
+            );
+            assert_eq!(skeleton.path(&["result", "value"]).unwrap().raw(), b"[]");
+        }
+    }
+
+    #[test]
+    fn stream_rejects_oversized_element() {
+        let mut skeleton = [0u8; 64];
+        let mut element = [0u8; 4];
+        let mut stream = ArrayStream::new(&mut skeleton, &mut element);
+        let result = stream.feed(br#"{"result":["abcdef"]}"#, |_| Ok(()));
+        assert!(matches!(result, Err(SdkError::ResponseParseError)));
+    }
+}
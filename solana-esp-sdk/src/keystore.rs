@@ -0,0 +1,999 @@
+use chacha20poly1305::{aead::AeadInPlace, ChaCha20Poly1305, Key, KeyInit, Nonce, Tag};
+#[cfg(any(test, feature = "std"))]
+use embedded_storage::nor_flash::{ErrorType, NorFlashErrorKind, ReadNorFlash};
+use embedded_storage::{nor_flash::NorFlash, ReadStorage, Storage};
+use sha2::Sha256;
+
+use crate::{
+    crypto::{Address, Keypair},
+    types::{Result, SdkError},
+};
+
+/// Identifies an encrypted keypair record.
+pub const MAGIC: &[u8; 4] = b"SESK";
+/// Current record format version.
+pub const VERSION: u8 = 1;
+/// Key derivation: PBKDF2-HMAC-SHA256.
+pub const KDF_PBKDF2_SHA256: u8 = 1;
+/// Default PBKDF2 iteration count. Use 1 when the secret is already a
+/// high-entropy device key rather than a passphrase.
+pub const DEFAULT_ITERATIONS: u32 = 10_000;
+/// Highest PBKDF2 iteration count accepted when sealing or loading a
+/// record, so a corrupted count cannot stall unlocking indefinitely.
+pub const MAX_ITERATIONS: u32 = 1_000_000;
+
+pub const SALT_LEN: usize = 16;
+pub const NONCE_LEN: usize = 12;
+const SEED_LEN: usize = 32;
+const TAG_LEN: usize = 16;
+
+// magic + version + kdf + iterations + salt + nonce + public key
+const HEADER_LEN: usize = MAGIC.len() + 1 + 1 + 4 + SALT_LEN + NONCE_LEN + 32;
+/// Size of an encoded record.
+pub const ENCODED_LEN: usize = HEADER_LEN + SEED_LEN + TAG_LEN;
+
+/// A `Keypair` seed encrypted with ChaCha20-Poly1305 under a key derived from
+/// a device secret or passphrase.
+///
+/// Wire layout:
+/// `magic | version | kdf | iterations (u32 LE) | salt | nonce | public key | ciphertext | tag`
+///
+/// The whole header is authenticated as associated data, so a tampered
+/// record fails to open instead of yielding a different key.
+#[derive(Clone)]
+pub struct EncryptedKeypair {
+    iterations: u32,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+    public_key: Address,
+    ciphertext: [u8; SEED_LEN],
+    tag: [u8; TAG_LEN],
+}
+
+impl EncryptedKeypair {
+    /// Encrypts the seed of `keypair`. `salt` and `nonce` must come from a
+    /// hardware RNG and never be reused.
+    pub fn seal(
+        keypair: &Keypair,
+        secret: &[u8],
+        iterations: u32,
+        salt: [u8; SALT_LEN],
+        nonce: [u8; NONCE_LEN],
+    ) -> Result<EncryptedKeypair> {
+        if !(1..=MAX_ITERATIONS).contains(&iterations) {
+            return Err(SdkError::Invalid);
+        }
+        let mut record = EncryptedKeypair {
+            iterations,
+            salt,
+            nonce,
+            public_key: *keypair.public_key(),
+            ciphertext: [0u8; SEED_LEN],
+            tag: [0u8; TAG_LEN],
+        };
+        record
+            .ciphertext
+            .copy_from_slice(&keypair.secret_key()[..SEED_LEN]);
+
+        let cipher = record.cipher(secret);
+        let header = record.header();
+        let tag = cipher
+            .encrypt_in_place_detached(
+                Nonce::from_slice(&record.nonce),
+                &header,
+                &mut record.ciphertext,
+            )
+            .map_err(|_| SdkError::Crypto)?;
+        record.tag.copy_from_slice(&tag);
+        Ok(record)
+    }
+
+    /// Decrypts the record and rebuilds the `Keypair`.
+    pub fn open(&self, secret: &[u8]) -> Result<Keypair> {
+        let cipher = self.cipher(secret);
+        let mut seed = self.ciphertext;
+        cipher
+            .decrypt_in_place_detached(
+                Nonce::from_slice(&self.nonce),
+                &self.header(),
+                &mut seed,
+                Tag::from_slice(&self.tag),
+            )
+            .map_err(|_| SdkError::Crypto)?;
+        let keypair = Keypair::new_from_seed(seed);
+        seed.fill(0);
+        if keypair.public_key() != &self.public_key {
+            return Err(SdkError::Crypto);
+        }
+        Ok(keypair)
+    }
+
+    /// Public key of the sealed keypair, readable without the secret.
+    pub fn public_key(&self) -> &Address {
+        &self.public_key
+    }
+
+    fn cipher(&self, secret: &[u8]) -> ChaCha20Poly1305 {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(secret, &self.salt, self.iterations, &mut key);
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        key.fill(0);
+        cipher
+    }
+
+    fn header(&self) -> [u8; HEADER_LEN] {
+        let mut header = [0u8; HEADER_LEN];
+        let mut pos = 0;
+        let mut put = |bytes: &[u8]| {
+            header[pos..pos + bytes.len()].copy_from_slice(bytes);
+            pos += bytes.len();
+        };
+        put(MAGIC);
+        put(&[VERSION, KDF_PBKDF2_SHA256]);
+        put(&self.iterations.to_le_bytes());
+        put(&self.salt);
+        put(&self.nonce);
+        put(self.public_key.as_ref());
+        header
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENCODED_LEN] {
+        let mut bytes = [0u8; ENCODED_LEN];
+        bytes[..HEADER_LEN].copy_from_slice(&self.header());
+        bytes[HEADER_LEN..HEADER_LEN + SEED_LEN].copy_from_slice(&self.ciphertext);
+        bytes[HEADER_LEN + SEED_LEN..].copy_from_slice(&self.tag);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<EncryptedKeypair> {
+        if bytes.len() < ENCODED_LEN || !bytes.starts_with(MAGIC) {
+            return Err(SdkError::Deserialize);
+        }
+        if bytes[4] != VERSION || bytes[5] != KDF_PBKDF2_SHA256 {
+            return Err(SdkError::Unsupported);
+        }
+        let take = |pos: usize, out: &mut [u8]| out.copy_from_slice(&bytes[pos..pos + out.len()]);
+
+        let mut iterations = [0u8; 4];
+        take(6, &mut iterations);
+        let iterations = u32::from_le_bytes(iterations);
+        // checked before any key derivation runs on the stored count
+        if !(1..=MAX_ITERATIONS).contains(&iterations) {
+            return Err(SdkError::Deserialize);
+        }
+        let mut record = EncryptedKeypair {
+            iterations,
+            salt: [0u8; SALT_LEN],
+            nonce: [0u8; NONCE_LEN],
+            public_key: Address::new([0u8; 32]),
+            ciphertext: [0u8; SEED_LEN],
+            tag: [0u8; TAG_LEN],
+        };
+        let mut public_key = [0u8; 32];
+        take(10, &mut record.salt);
+        take(10 + SALT_LEN, &mut record.nonce);
+        take(10 + SALT_LEN + NONCE_LEN, &mut public_key);
+        take(HEADER_LEN, &mut record.ciphertext);
+        take(HEADER_LEN + SEED_LEN, &mut record.tag);
+        record.public_key = Address::new(public_key);
+        Ok(record)
+    }
+
+    /// Writes the record at `offset` of any `embedded-storage` backend,
+    /// e.g. `esp_storage::FlashStorage`.
+    pub fn store<S: Storage>(&self, storage: &mut S, offset: u32) -> Result<()> {
+        storage
+            .write(offset, &self.to_bytes())
+            .map_err(|_| SdkError::Storage)
+    }
+
+    /// Reads a record previously written with `store`.
+    pub fn load<S: ReadStorage>(storage: &mut S, offset: u32) -> Result<EncryptedKeypair> {
+        let mut bytes = [0u8; ENCODED_LEN];
+        storage
+            .read(offset, &mut bytes)
+            .map_err(|_| SdkError::Storage)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// RAM-backed `embedded-storage` implementation for host tests; needs the
+/// `std` feature outside this crate's tests.
+#[cfg(any(test, feature = "std"))]
+pub struct MemoryStorage<const N: usize> {
+    data: [u8; N],
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const N: usize> MemoryStorage<N> {
+    /// Creates storage filled with `0xff`, like erased flash.
+    pub fn new() -> Self {
+        Self { data: [0xff; N] }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.data
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const N: usize> Default for MemoryStorage<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const N: usize> ReadStorage for MemoryStorage<N> {
+    type Error = SdkError;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<()> {
+        let start = offset as usize;
+        let data = self
+            .data
+            .get(start..start + bytes.len())
+            .ok_or(SdkError::Invalid)?;
+        bytes.copy_from_slice(data);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const N: usize> Storage for MemoryStorage<N> {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<()> {
+        let start = offset as usize;
+        self.data
+            .get_mut(start..start + bytes.len())
+            .ok_or(SdkError::Invalid)?
+            .copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Maximum label length in bytes.
+pub const MAX_LABEL_LEN: usize = 32;
+
+/// Persistent, label-addressed storage of encrypted keypairs.
+pub trait KeyStore {
+    /// Stores `record` under `label`, replacing any previous record.
+    fn store(&mut self, label: &str, record: &EncryptedKeypair) -> Result<()>;
+
+    /// Returns the record stored under `label`, if any.
+    fn load(&mut self, label: &str) -> Result<Option<EncryptedKeypair>>;
+
+    /// Removes `label`. Returns whether it existed.
+    fn delete(&mut self, label: &str) -> Result<bool>;
+
+    /// Calls `visit` with the label and public key of every stored record.
+    fn list<F: FnMut(&str, &Address)>(&mut self, visit: F) -> Result<()>;
+
+    /// Loads and decrypts the keypair stored under `label`.
+    fn load_keypair(&mut self, label: &str, secret: &[u8]) -> Result<Option<Keypair>> {
+        self.load(label)?
+            .map(|record| record.open(secret))
+            .transpose()
+    }
+}
+
+fn check_label(label: &str) -> Result<()> {
+    if label.is_empty() || label.len() > MAX_LABEL_LEN {
+        return Err(SdkError::Invalid);
+    }
+    Ok(())
+}
+
+/// `KeyStore` kept in RAM, holding up to `N` records.
+pub struct MemoryKeyStore<const N: usize> {
+    records: heapless::Vec<(heapless::String<MAX_LABEL_LEN>, EncryptedKeypair), N>,
+}
+
+impl<const N: usize> MemoryKeyStore<N> {
+    pub fn new() -> Self {
+        Self {
+            records: heapless::Vec::new(),
+        }
+    }
+}
+
+impl<const N: usize> Default for MemoryKeyStore<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> KeyStore for MemoryKeyStore<N> {
+    fn store(&mut self, label: &str, record: &EncryptedKeypair) -> Result<()> {
+        check_label(label)?;
+        if let Some((_, existing)) = self.records.iter_mut().find(|(l, _)| l == label) {
+            *existing = record.clone();
+            return Ok(());
+        }
+        let mut owned = heapless::String::new();
+        // length checked by check_label
+        let _ = owned.push_str(label);
+        self.records
+            .push((owned, record.clone()))
+            .map_err(|_| SdkError::Storage)
+    }
+
+    fn load(&mut self, label: &str) -> Result<Option<EncryptedKeypair>> {
+        Ok(self
+            .records
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, record)| record.clone()))
+    }
+
+    fn delete(&mut self, label: &str) -> Result<bool> {
+        match self.records.iter().position(|(l, _)| l == label) {
+            Some(index) => {
+                self.records.swap_remove(index);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn list<F: FnMut(&str, &Address)>(&mut self, mut visit: F) -> Result<()> {
+        for (label, record) in self.records.iter() {
+            visit(label, record.public_key());
+        }
+        Ok(())
+    }
+}
+
+/// Maximum number of erase sectors a `FlashKeyStore` can span.
+pub const MAX_SECTORS: usize = 16;
+/// Size of one record slot in flash.
+pub const SLOT_LEN: usize = 160;
+
+const SECTOR_MAGIC: &[u8; 4] = b"SKS1";
+const SECTOR_HEADER_LEN: usize = 16;
+const SLOT_PUT: u8 = 1;
+const SLOT_DELETE: u8 = 2;
+// kind + label length + label + record
+const SLOT_BODY_LEN: usize = 2 + MAX_LABEL_LEN + ENCODED_LEN;
+
+enum Slot {
+    /// Erased; this and every later slot of the sector are unused.
+    Free,
+    /// Partially written or damaged; skipped.
+    Corrupt,
+    Entry([u8; SLOT_LEN]),
+}
+
+fn slot_label(slot: &[u8; SLOT_LEN]) -> &[u8] {
+    let len = (slot[1] as usize).min(MAX_LABEL_LEN);
+    &slot[2..2 + len]
+}
+
+/// `KeyStore` over a range of NOR flash erase sectors, e.g.
+/// `esp_storage::FlashStorage`.
+///
+/// Records are appended to a log of fixed-size slots, each covered by a
+/// CRC32, so an interrupted write leaves a slot that is skipped on the next
+/// scan and the previous record stays in effect. Sectors are filled in turn
+/// and one sector is always kept erased; when the active sector fills up the
+/// spare becomes active, the live records of the oldest sector are copied
+/// into it and the oldest sector is erased. Every sector is therefore erased
+/// at the same rate.
+pub struct FlashKeyStore<F> {
+    flash: F,
+    base: u32,
+    sectors: u32,
+    active: u32,
+    active_seq: u32,
+    next_slot: u32,
+}
+
+impl<F: NorFlash> FlashKeyStore<F> {
+    /// Mounts the store on `sectors` erase sectors starting at `base`,
+    /// formatting the range if it holds no store yet.
+    pub fn new(flash: F, base: u32, sectors: u32) -> Result<Self> {
+        let aligned = |n: usize| n.is_multiple_of(F::WRITE_SIZE) && n.is_multiple_of(F::READ_SIZE);
+        if !aligned(SLOT_LEN) || !aligned(SECTOR_HEADER_LEN) {
+            return Err(SdkError::Unsupported);
+        }
+        if F::ERASE_SIZE < SECTOR_HEADER_LEN + 2 * SLOT_LEN
+            || !(base as usize).is_multiple_of(F::ERASE_SIZE)
+            || !(2..=MAX_SECTORS as u32).contains(&sectors)
+            || base as usize + sectors as usize * F::ERASE_SIZE > flash.capacity()
+        {
+            return Err(SdkError::Invalid);
+        }
+
+        let mut store = Self {
+            flash,
+            base,
+            sectors,
+            active: 0,
+            active_seq: 0,
+            next_slot: 0,
+        };
+        store.mount()?;
+        Ok(store)
+    }
+
+    /// Releases the underlying flash.
+    pub fn into_inner(self) -> F {
+        self.flash
+    }
+
+    /// Maximum number of labels the store accepts. Half a sector, so the
+    /// live records of the oldest sector always fit into a fresh one.
+    pub fn capacity(&self) -> usize {
+        Self::slots_per_sector() as usize / 2
+    }
+
+    fn slots_per_sector() -> u32 {
+        ((F::ERASE_SIZE - SECTOR_HEADER_LEN) / SLOT_LEN) as u32
+    }
+
+    fn sector_addr(&self, sector: u32) -> u32 {
+        self.base + sector * F::ERASE_SIZE as u32
+    }
+
+    fn slot_addr(&self, sector: u32, slot: u32) -> u32 {
+        self.sector_addr(sector) + (SECTOR_HEADER_LEN + slot as usize * SLOT_LEN) as u32
+    }
+
+    fn mount(&mut self) -> Result<()> {
+        let order = self.sector_order()?;
+        let Some(&(seq, sector)) = order.last() else {
+            return self.start_sector(0, 1);
+        };
+        self.active = sector;
+        self.active_seq = seq;
+        self.next_slot = Self::slots_per_sector();
+        for slot in 0..Self::slots_per_sector() {
+            if let Slot::Free = self.read_slot(sector, slot)? {
+                self.next_slot = slot;
+                break;
+            }
+        }
+        // a collection was interrupted before the oldest sector got erased
+        if order.len() == self.sectors as usize {
+            self.collect(order[0].1)?;
+        }
+        Ok(())
+    }
+
+    /// Sectors holding a valid header, oldest first, as `(seq, sector)`.
+    fn sector_order(&mut self) -> Result<heapless::Vec<(u32, u32), MAX_SECTORS>> {
+        let mut order: heapless::Vec<(u32, u32), MAX_SECTORS> = heapless::Vec::new();
+        for sector in 0..self.sectors {
+            let mut header = [0u8; SECTOR_HEADER_LEN];
+            self.flash
+                .read(self.sector_addr(sector), &mut header)
+                .map_err(|_| SdkError::Storage)?;
+            if &header[..4] != SECTOR_MAGIC || header[8..12] != crc32(&header[..8]).to_le_bytes() {
+                continue;
+            }
+            let seq = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            // at most `sectors` entries, bounded by MAX_SECTORS
+            let _ = order.push((seq, sector));
+        }
+        order.sort_unstable();
+        Ok(order)
+    }
+
+    fn start_sector(&mut self, sector: u32, seq: u32) -> Result<()> {
+        let addr = self.sector_addr(sector);
+        self.flash
+            .erase(addr, addr + F::ERASE_SIZE as u32)
+            .map_err(|_| SdkError::Storage)?;
+        let mut header = [0xffu8; SECTOR_HEADER_LEN];
+        header[..4].copy_from_slice(SECTOR_MAGIC);
+        header[4..8].copy_from_slice(&seq.to_le_bytes());
+        let crc = crc32(&header[..8]);
+        header[8..12].copy_from_slice(&crc.to_le_bytes());
+        self.flash
+            .write(addr, &header)
+            .map_err(|_| SdkError::Storage)?;
+        self.active = sector;
+        self.active_seq = seq;
+        self.next_slot = 0;
+        Ok(())
+    }
+
+    fn read_slot(&mut self, sector: u32, slot: u32) -> Result<Slot> {
+        let mut bytes = [0u8; SLOT_LEN];
+        self.flash
+            .read(self.slot_addr(sector, slot), &mut bytes)
+            .map_err(|_| SdkError::Storage)?;
+        if bytes.iter().all(|&b| b == 0xff) {
+            return Ok(Slot::Free);
+        }
+        let crc = crc32(&bytes[..SLOT_BODY_LEN]).to_le_bytes();
+        if bytes[SLOT_BODY_LEN..SLOT_BODY_LEN + 4] != crc
+            || !matches!(bytes[0], SLOT_PUT | SLOT_DELETE)
+            || bytes[1] as usize > MAX_LABEL_LEN
+        {
+            return Ok(Slot::Corrupt);
+        }
+        Ok(Slot::Entry(bytes))
+    }
+
+    fn append(&mut self, slot: &[u8; SLOT_LEN]) -> Result<()> {
+        if self.next_slot == Self::slots_per_sector() {
+            self.rotate()?;
+        }
+        let addr = self.slot_addr(self.active, self.next_slot);
+        // the slot is consumed even if the write fails halfway
+        self.next_slot += 1;
+        self.flash.write(addr, slot).map_err(|_| SdkError::Storage)
+    }
+
+    /// Moves to the spare sector, then collects the oldest one so a spare
+    /// exists again.
+    fn rotate(&mut self) -> Result<()> {
+        let order = self.sector_order()?;
+        let spare = (0..self.sectors)
+            .find(|sector| order.iter().all(|&(_, s)| s != *sector))
+            .ok_or(SdkError::Storage)?;
+        self.start_sector(spare, self.active_seq.wrapping_add(1))?;
+        if order.len() + 1 == self.sectors as usize {
+            self.collect(order[0].1)?;
+        }
+        Ok(())
+    }
+
+    /// Copies the records of `sector` that are still current into the active
+    /// sector, then erases it. Safe to repeat after a power loss: records
+    /// already copied are no longer current in `sector`.
+    fn collect(&mut self, sector: u32) -> Result<()> {
+        for slot in 0..Self::slots_per_sector() {
+            let entry = match self.read_slot(sector, slot)? {
+                Slot::Free => break,
+                Slot::Corrupt => continue,
+                Slot::Entry(entry) => entry,
+            };
+            if entry[0] == SLOT_PUT && !self.superseded(sector, slot, slot_label(&entry))? {
+                if self.next_slot == Self::slots_per_sector() {
+                    return Err(SdkError::Storage);
+                }
+                self.append(&entry)?;
+            }
+        }
+        let addr = self.sector_addr(sector);
+        self.flash
+            .erase(addr, addr + F::ERASE_SIZE as u32)
+            .map_err(|_| SdkError::Storage)
+    }
+
+    /// Whether a record for `label` was written after `(sector, slot)`.
+    fn superseded(&mut self, sector: u32, slot: u32, label: &[u8]) -> Result<bool> {
+        let order = self.sector_order()?;
+        let start = order
+            .iter()
+            .position(|&(_, s)| s == sector)
+            .ok_or(SdkError::Storage)?;
+        let mut first = slot + 1;
+        for &(_, s) in order[start..].iter() {
+            for i in first..Self::slots_per_sector() {
+                match self.read_slot(s, i)? {
+                    Slot::Free => break,
+                    Slot::Corrupt => {}
+                    Slot::Entry(entry) => {
+                        if slot_label(&entry) == label {
+                            return Ok(true);
+                        }
+                    }
+                }
+            }
+            first = 0;
+        }
+        Ok(false)
+    }
+
+    /// Latest record written for `label`, put or delete.
+    fn find(&mut self, label: &[u8]) -> Result<Option<[u8; SLOT_LEN]>> {
+        let mut found = None;
+        for (_, sector) in self.sector_order()? {
+            for slot in 0..Self::slots_per_sector() {
+                match self.read_slot(sector, slot)? {
+                    Slot::Free => break,
+                    Slot::Corrupt => {}
+                    Slot::Entry(entry) => {
+                        if slot_label(&entry) == label {
+                            found = Some(entry);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn write_entry(
+        &mut self,
+        kind: u8,
+        label: &str,
+        record: Option<&EncryptedKeypair>,
+    ) -> Result<()> {
+        let mut entry = [0xffu8; SLOT_LEN];
+        entry[0] = kind;
+        entry[1] = label.len() as u8;
+        entry[2..2 + MAX_LABEL_LEN].fill(0);
+        entry[2..2 + label.len()].copy_from_slice(label.as_bytes());
+        match record {
+            Some(record) => {
+                entry[2 + MAX_LABEL_LEN..SLOT_BODY_LEN].copy_from_slice(&record.to_bytes())
+            }
+            None => entry[2 + MAX_LABEL_LEN..SLOT_BODY_LEN].fill(0),
+        }
+        let crc = crc32(&entry[..SLOT_BODY_LEN]);
+        entry[SLOT_BODY_LEN..SLOT_BODY_LEN + 4].copy_from_slice(&crc.to_le_bytes());
+        self.append(&entry)
+    }
+
+    fn count(&mut self) -> Result<usize> {
+        let mut count = 0;
+        self.list(|_, _| count += 1)?;
+        Ok(count)
+    }
+}
+
+impl<F: NorFlash> KeyStore for FlashKeyStore<F> {
+    fn store(&mut self, label: &str, record: &EncryptedKeypair) -> Result<()> {
+        check_label(label)?;
+        let exists = matches!(self.find(label.as_bytes())?, Some(entry) if entry[0] == SLOT_PUT);
+        if !exists && self.count()? >= self.capacity() {
+            return Err(SdkError::Storage);
+        }
+        self.write_entry(SLOT_PUT, label, Some(record))
+    }
+
+    fn load(&mut self, label: &str) -> Result<Option<EncryptedKeypair>> {
+        match self.find(label.as_bytes())? {
+            Some(entry) if entry[0] == SLOT_PUT => {
+                EncryptedKeypair::from_bytes(&entry[2 + MAX_LABEL_LEN..SLOT_BODY_LEN]).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn delete(&mut self, label: &str) -> Result<bool> {
+        check_label(label)?;
+        match self.find(label.as_bytes())? {
+            Some(entry) if entry[0] == SLOT_PUT => {
+                self.write_entry(SLOT_DELETE, label, None)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn list<V: FnMut(&str, &Address)>(&mut self, mut visit: V) -> Result<()> {
+        for (_, sector) in self.sector_order()? {
+            for slot in 0..Self::slots_per_sector() {
+                let entry = match self.read_slot(sector, slot)? {
+                    Slot::Free => break,
+                    Slot::Corrupt => continue,
+                    Slot::Entry(entry) => entry,
+                };
+                if entry[0] != SLOT_PUT || self.superseded(sector, slot, slot_label(&entry))? {
+                    continue;
+                }
+                let Ok(label) = core::str::from_utf8(slot_label(&entry)) else {
+                    continue;
+                };
+                if let Ok(record) =
+                    EncryptedKeypair::from_bytes(&entry[2 + MAX_LABEL_LEN..SLOT_BODY_LEN])
+                {
+                    visit(label, record.public_key());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// CRC-32 (IEEE 802.3), bitwise to keep flash usage small.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// RAM-backed NOR flash with `SIZE` bytes and `ERASE`-byte sectors. Writes
+/// can only clear bits, as on real flash, so host tests exercise the same
+/// constraints as the device. Needs the `std` feature outside this crate's
+/// tests.
+#[cfg(any(test, feature = "std"))]
+pub struct MemoryFlash<const SIZE: usize, const ERASE: usize> {
+    data: [u8; SIZE],
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const SIZE: usize, const ERASE: usize> MemoryFlash<SIZE, ERASE> {
+    /// Creates fully erased flash.
+    pub fn new() -> Self {
+        Self { data: [0xff; SIZE] }
+    }
+
+    pub fn as_bytes(&self) -> &[u8; SIZE] {
+        &self.data
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8; SIZE] {
+        &mut self.data
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const SIZE: usize, const ERASE: usize> Default for MemoryFlash<SIZE, ERASE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const SIZE: usize, const ERASE: usize> ErrorType for MemoryFlash<SIZE, ERASE> {
+    type Error = NorFlashErrorKind;
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const SIZE: usize, const ERASE: usize> ReadNorFlash for MemoryFlash<SIZE, ERASE> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> core::result::Result<(), Self::Error> {
+        let start = offset as usize;
+        let data = self
+            .data
+            .get(start..start + bytes.len())
+            .ok_or(NorFlashErrorKind::OutOfBounds)?;
+        bytes.copy_from_slice(data);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+#[cfg(any(test, feature = "std"))]
+impl<const SIZE: usize, const ERASE: usize> NorFlash for MemoryFlash<SIZE, ERASE> {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = ERASE;
+
+    fn erase(&mut self, from: u32, to: u32) -> core::result::Result<(), Self::Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from % ERASE != 0 || to % ERASE != 0 {
+            return Err(NorFlashErrorKind::NotAligned);
+        }
+        self.data
+            .get_mut(from..to)
+            .ok_or(NorFlashErrorKind::OutOfBounds)?
+            .fill(0xff);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> core::result::Result<(), Self::Error> {
+        let start = offset as usize;
+        if !start.is_multiple_of(Self::WRITE_SIZE) || !bytes.len().is_multiple_of(Self::WRITE_SIZE)
+        {
+            return Err(NorFlashErrorKind::NotAligned);
+        }
+        let data = self
+            .data
+            .get_mut(start..start + bytes.len())
+            .ok_or(NorFlashErrorKind::OutOfBounds)?;
+        if data.iter().zip(bytes).any(|(old, new)| old & new != *new) {
+            return Err(NorFlashErrorKind::Other);
+        }
+        data.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestFlash = MemoryFlash<{ 4096 * 3 }, 4096>;
+
+    fn sealed(iterations: u32) -> EncryptedKeypair {
+        sealed_seed(7, iterations)
+    }
+
+    fn sealed_seed(seed: u8, iterations: u32) -> EncryptedKeypair {
+        let keypair = Keypair::new_from_seed([seed; 32]);
+        EncryptedKeypair::seal(
+            &keypair,
+            b"secret",
+            iterations,
+            [1; SALT_LEN],
+            [2; NONCE_LEN],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn seals_and_opens() {
+        let record = sealed(1);
+        let mut storage = MemoryStorage::<256>::new();
+        record.store(&mut storage, 10).unwrap();
+        let loaded = EncryptedKeypair::load(&mut storage, 10).unwrap();
+        let keypair = loaded.open(b"secret").unwrap();
+        assert_eq!(keypair.public_key(), record.public_key());
+        assert!(loaded.open(b"wrong").is_err());
+
+        let mut tampered = record.to_bytes();
+        tampered[HEADER_LEN - 1] ^= 1;
+        let tampered = EncryptedKeypair::from_bytes(&tampered).unwrap();
+        assert!(tampered.open(b"secret").is_err());
+    }
+
+    #[test]
+    fn bounds_iteration_count() {
+        let keypair = Keypair::new_from_seed([7; 32]);
+        for iterations in [0, MAX_ITERATIONS + 1] {
+            assert!(EncryptedKeypair::seal(
+                &keypair,
+                b"secret",
+                iterations,
+                [1; SALT_LEN],
+                [2; NONCE_LEN]
+            )
+            .is_err());
+        }
+
+        let bytes = sealed(1).to_bytes();
+        for iterations in [0, MAX_ITERATIONS + 1, u32::MAX] {
+            let mut corrupted = bytes;
+            corrupted[6..10].copy_from_slice(&iterations.to_le_bytes());
+            assert!(matches!(
+                EncryptedKeypair::from_bytes(&corrupted),
+                Err(SdkError::Deserialize)
+            ));
+        }
+        let mut at_limit = bytes;
+        at_limit[6..10].copy_from_slice(&MAX_ITERATIONS.to_le_bytes());
+        assert!(EncryptedKeypair::from_bytes(&at_limit).is_ok());
+    }
+
+    fn flash_store() -> FlashKeyStore<TestFlash> {
+        FlashKeyStore::new(TestFlash::new(), 0, 3).unwrap()
+    }
+
+    fn loaded_key(store: &mut FlashKeyStore<TestFlash>, label: &str) -> Option<Address> {
+        store
+            .load(label)
+            .unwrap()
+            .map(|record| *record.public_key())
+    }
+
+    #[test]
+    fn flash_overwrites_and_deletes() {
+        let mut store = flash_store();
+        let first = sealed_seed(1, 1);
+        let second = sealed_seed(2, 1);
+        store.store("a", &first).unwrap();
+        store.store("b", &first).unwrap();
+        store.store("a", &second).unwrap();
+        assert_eq!(loaded_key(&mut store, "a"), Some(*second.public_key()));
+        assert_eq!(loaded_key(&mut store, "b"), Some(*first.public_key()));
+
+        assert!(store.delete("a").unwrap());
+        assert!(!store.delete("a").unwrap());
+        assert!(!store.delete("missing").unwrap());
+        assert_eq!(loaded_key(&mut store, "a"), None);
+
+        let mut labels = heapless::Vec::<heapless::String<MAX_LABEL_LEN>, 4>::new();
+        store
+            .list(|label, _| labels.push(label.try_into().unwrap()).unwrap())
+            .unwrap();
+        assert_eq!(labels, ["b"]);
+
+        // state survives a remount
+        let mut store = FlashKeyStore::new(store.into_inner(), 0, 3).unwrap();
+        assert_eq!(loaded_key(&mut store, "a"), None);
+        assert_eq!(loaded_key(&mut store, "b"), Some(*first.public_key()));
+        store.store("a", &first).unwrap();
+        assert_eq!(loaded_key(&mut store, "a"), Some(*first.public_key()));
+    }
+
+    #[test]
+    fn flash_enforces_capacity() {
+        let mut store = flash_store();
+        let record = sealed(1);
+        let labels = [
+            "k0", "k1", "k2", "k3", "k4", "k5", "k6", "k7", "k8", "k9", "ka", "kb",
+        ];
+        assert_eq!(store.capacity(), labels.len());
+        for label in labels {
+            store.store(label, &record).unwrap();
+        }
+        assert!(matches!(store.store("kc", &record), Err(SdkError::Storage)));
+        // overwriting an existing label needs no extra room
+        store.store("k0", &record).unwrap();
+        assert!(store.delete("k0").unwrap());
+        store.store("kc", &record).unwrap();
+    }
+
+    #[test]
+    fn flash_skips_torn_slot() {
+        let mut store = flash_store();
+        let first = sealed_seed(1, 1);
+        let second = sealed_seed(2, 1);
+        store.store("a", &first).unwrap();
+        store.store("a", &second).unwrap();
+
+        // the second write lost power halfway: its tail is still erased
+        let mut flash = store.into_inner();
+        let torn = SECTOR_HEADER_LEN + SLOT_LEN;
+        flash.as_bytes_mut()[torn + SLOT_LEN / 2..torn + SLOT_LEN].fill(0xff);
+
+        let mut store = FlashKeyStore::new(flash, 0, 3).unwrap();
+        assert_eq!(loaded_key(&mut store, "a"), Some(*first.public_key()));
+        let mut count = 0;
+        store.list(|_, _| count += 1).unwrap();
+        assert_eq!(count, 1);
+
+        // new writes go after the torn slot
+        store.store("a", &second).unwrap();
+        assert_eq!(loaded_key(&mut store, "a"), Some(*second.public_key()));
+        let flash = store.into_inner();
+        let next = torn + SLOT_LEN;
+        assert_eq!(flash.as_bytes()[next], SLOT_PUT);
+
+        // a flipped bit fails the CRC the same way
+        let mut flash = flash;
+        flash.as_bytes_mut()[next + 40] ^= 1;
+        let mut store = FlashKeyStore::new(flash, 0, 3).unwrap();
+        assert_eq!(loaded_key(&mut store, "a"), Some(*first.public_key()));
+    }
+
+    #[test]
+    fn flash_rotates_sectors() {
+        let mut store = flash_store();
+        let records = [sealed_seed(1, 1), sealed_seed(2, 1), sealed_seed(3, 1)];
+        store.store("kept", &records[0]).unwrap();
+        store.store("gone", &records[0]).unwrap();
+        assert!(store.delete("gone").unwrap());
+
+        // enough writes to fill every sector more than once
+        let slots = FlashKeyStore::<TestFlash>::slots_per_sector() as usize;
+        for i in 0..slots * 4 {
+            store.store("hot", &records[i % 3]).unwrap();
+            assert_eq!(
+                loaded_key(&mut store, "hot"),
+                Some(*records[i % 3].public_key())
+            );
+        }
+        let latest = *records[(slots * 4 - 1) % 3].public_key();
+        assert_eq!(
+            loaded_key(&mut store, "kept"),
+            Some(*records[0].public_key())
+        );
+        assert_eq!(loaded_key(&mut store, "gone"), None);
+        assert_eq!(loaded_key(&mut store, "hot"), Some(latest));
+
+        // the oldest sector was collected, so one sector is always spare
+        assert_eq!(store.sector_order().unwrap().len(), 2);
+
+        let mut store = FlashKeyStore::new(store.into_inner(), 0, 3).unwrap();
+        assert_eq!(
+            loaded_key(&mut store, "kept"),
+            Some(*records[0].public_key())
+        );
+        assert_eq!(loaded_key(&mut store, "gone"), None);
+        assert_eq!(loaded_key(&mut store, "hot"), Some(latest));
+        let mut labels = heapless::Vec::<heapless::String<MAX_LABEL_LEN>, 4>::new();
+        store
+            .list(|label, _| labels.push(label.try_into().unwrap()).unwrap())
+            .unwrap();
+        labels.sort_unstable();
+        assert_eq!(labels, ["hot", "kept"]);
+    }
+}
@@ -13,8 +13,34 @@ pub mod crypto;
 
 pub mod transaction;
 
+pub mod transaction_error;
+
+pub mod compute_budget;
+
+pub mod token;
+
+pub mod nonce;
+
+pub mod offchain;
+
+pub mod siws;
+
+#[cfg(feature = "keystore")]
+#[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+pub mod keystore;
+
+#[cfg(feature = "x25519")]
+#[cfg_attr(docsrs, doc(cfg(feature = "x25519")))]
+pub mod envelope;
+
+pub mod json;
+
 pub mod rpc;
 
+pub mod cluster;
+
+pub mod clock;
+
 #[cfg(any(feature = "net-smoltcp", feature = "net-reqwless"))]
 #[cfg_attr(
     docsrs,
@@ -24,7 +50,7 @@ pub mod net;
 
 pub mod prelude {
 
-    pub use crate::types::{Result, SdkError};
+    pub use crate::types::{Result, RpcError, RpcErrorKind, SdkError};
 
     pub use crate::crypto::*;
 
@@ -32,5 +58,21 @@ pub mod prelude {
 
     pub use crate::transaction::*;
 
+    pub use crate::transaction_error::*;
+
+    pub use crate::compute_budget::*;
+
+    pub use crate::token::*;
+
+    pub use crate::nonce::*;
+
+    pub use crate::offchain::*;
+
+    pub use crate::siws::*;
+
     pub use crate::rpc::*;
+
+    pub use crate::cluster::*;
+
+    pub use crate::clock::*;
 }
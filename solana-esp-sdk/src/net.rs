@@ -1,4 +1,6 @@
 #[cfg(feature = "net-reqwless")]
+use embedded_io_async::Read;
+#[cfg(feature = "net-reqwless")]
 use embedded_nal_async::{Dns, TcpConnect};
 #[cfg(feature = "net-reqwless")]
 use reqwless::{
@@ -7,7 +9,7 @@ use reqwless::{
     request::RequestBuilder,
 };
 
-use crate::rpc::AsyncClient;
+use crate::rpc::{AsyncClient, AsyncStreamingClient};
 use crate::types::{Result, SdkError};
 
 /// A reqwless-based RpcAsyncClient (no_std HTTP client for embedded).
@@ -57,6 +59,53 @@ impl<T: TcpConnect, D: Dns> AsyncClient for ReqwlessAsyncClient<T, D> {
     }
 }
 
+#[cfg(feature = "net-reqwless")]
+impl<T: TcpConnect, D: Dns> AsyncStreamingClient for ReqwlessAsyncClient<T, D> {
+    async fn post_json_streaming(
+        &self,
+        url: &str,
+        json_body: &[u8],
+        mut on_chunk: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let mut rx_buffer = [0; 4096];
+        let mut tx_buffer = [0; 4096];
+
+        let tls = TlsConfig::new(
+            self.tls_seed,
+            &mut rx_buffer,
+            &mut tx_buffer,
+            reqwless::client::TlsVerify::None,
+        );
+
+        let mut client = HttpClient::new_with_tls(&self.tcp, &self.dns, tls);
+        let mut http_req = client
+            .request(reqwless::request::Method::POST, url)
+            .await
+            .map_err(|_| SdkError::NetworkError)?
+            .body(json_body)
+            .content_type(ContentType::ApplicationJson);
+        // holds the response headers, then each chunk of the body
+        let mut buffer = [0; 1024];
+        let response = http_req
+            .send(&mut buffer)
+            .await
+            .map_err(|_| SdkError::NetworkError)?;
+
+        let mut reader = response.body().reader();
+        let mut chunk = [0; 512];
+        loop {
+            let len = reader
+                .read(&mut chunk)
+                .await
+                .map_err(|_| SdkError::NetworkError)?;
+            if len == 0 {
+                return Ok(());
+            }
+            on_chunk(&chunk[..len])?;
+        }
+    }
+}
+
 /*
 /// A smoltcp + manual HTTP/1.1 POST client for ultra-small builds.
 #[cfg(feature = "net-smoltcp")]
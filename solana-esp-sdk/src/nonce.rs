@@ -0,0 +1,44 @@
+use crate::{
+    crypto::Address,
+    hash::Hash,
+    types::{Result, SdkError},
+};
+
+/// Size of a durable nonce account.
+pub const NONCE_ACCOUNT_LEN: usize = 80;
+
+/// Initialized durable nonce account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceAccount {
+    /// Key allowed to advance or withdraw from the nonce.
+    pub authority: Address,
+    /// Stored nonce, used in place of a recent blockhash.
+    pub durable_nonce: Hash,
+    /// Fee per signature when the nonce was stored.
+    pub lamports_per_signature: u64,
+}
+
+impl NonceAccount {
+    /// Decodes the system program's nonce layout; fails on uninitialized
+    /// accounts.
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let data = data.get(..NONCE_ACCOUNT_LEN).ok_or(SdkError::Deserialize)?;
+        let version = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        let state = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+        // versions 0 (legacy) and 1 (current) share the layout
+        if version > 1 || state != 1 {
+            return Err(SdkError::Deserialize);
+        }
+        let mut authority = [0u8; 32];
+        authority.copy_from_slice(&data[8..40]);
+        let mut durable_nonce = [0u8; 32];
+        durable_nonce.copy_from_slice(&data[40..72]);
+        let mut lamports_per_signature = [0u8; 8];
+        lamports_per_signature.copy_from_slice(&data[72..80]);
+        Ok(NonceAccount {
+            authority: Address::new(authority),
+            durable_nonce: Hash::from(durable_nonce),
+            lamports_per_signature: u64::from_le_bytes(lamports_per_signature),
+        })
+    }
+}
@@ -0,0 +1,554 @@
+use crate::{
+    crypto::{Address, Keypair},
+    types::{Result, SdkError},
+};
+
+/// Prefix prepended to every off-chain message. The leading `0xff` byte can
+/// never start a valid transaction message, so a signed off-chain message can
+/// not be replayed on-chain.
+pub const SIGNING_DOMAIN: &[u8; 16] = b"\xffsolana offchain";
+/// Only header version currently defined by the standard.
+pub const HEADER_VERSION: u8 = 0;
+/// Maximum number of signers a message may declare.
+pub const MAX_SIGNERS: usize = u8::MAX as usize;
+/// Maximum total size (header included) that hardware wallets accept.
+pub const MAX_LEN_LEDGER: usize = 1232;
+/// Maximum message body size allowed by the `u16` length field.
+pub const MAX_LEN: usize = u16::MAX as usize;
+
+// signing domain + version + application domain + format + signer count + length
+const FIXED_HEADER_LEN: usize = SIGNING_DOMAIN.len() + 1 + 32 + 1 + 1 + 2;
+
+/// Header size of the layout `solana sign-offchain-message` produces.
+pub const LEGACY_HEADER_LEN: usize = SIGNING_DOMAIN.len() + 1 + 1 + 2;
+/// Largest body of a ledger-sized message in the CLI layout.
+pub const LEGACY_MAX_LEN_LEDGER: usize = MAX_LEN_LEDGER - LEGACY_HEADER_LEN;
+/// Largest body in the CLI layout.
+pub const LEGACY_MAX_LEN: usize = MAX_LEN - LEGACY_HEADER_LEN;
+
+/// Encoding restriction declared in the message header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageFormat {
+    /// Printable ASCII (0x20..=0x7e), total size within `MAX_LEN_LEDGER`.
+    RestrictedAscii = 0,
+    /// UTF-8, total size within `MAX_LEN_LEDGER`.
+    LimitedUtf8 = 1,
+    /// UTF-8, body up to `MAX_LEN` bytes.
+    ExtendedUtf8 = 2,
+}
+
+impl TryFrom<u8> for MessageFormat {
+    type Error = SdkError;
+
+    fn try_from(value: u8) -> Result<Self> {
+        match value {
+            0 => Ok(MessageFormat::RestrictedAscii),
+            1 => Ok(MessageFormat::LimitedUtf8),
+            2 => Ok(MessageFormat::ExtendedUtf8),
+            _ => Err(SdkError::Invalid),
+        }
+    }
+}
+
+/// Solana off-chain message (header version 0).
+///
+/// Wire layout:
+/// `signing domain | version | application domain | format | signer count | signers | length (u16 LE) | body`
+#[derive(Debug, Clone)]
+pub struct OffchainMessage<'a> {
+    application_domain: [u8; 32],
+    format: MessageFormat,
+    signers: &'a [Address],
+    message: &'a [u8],
+}
+
+impl<'a> OffchainMessage<'a> {
+    /// Builds a message, picking the most restrictive format the body fits in.
+    pub fn new(
+        application_domain: [u8; 32],
+        signers: &'a [Address],
+        message: &'a [u8],
+    ) -> Result<Self> {
+        let total_len = Self::header_len(signers.len()) + message.len();
+        let format = pick_format(message, total_len <= MAX_LEN_LEDGER);
+        Self::new_with_format(application_domain, format, signers, message)
+    }
+
+    /// Builds a message with an explicit format, validating the body against it.
+    pub fn new_with_format(
+        application_domain: [u8; 32],
+        format: MessageFormat,
+        signers: &'a [Address],
+        message: &'a [u8],
+    ) -> Result<Self> {
+        if signers.is_empty() || signers.len() > MAX_SIGNERS {
+            return Err(SdkError::Invalid);
+        }
+        if message.is_empty() || message.len() > MAX_LEN {
+            return Err(SdkError::Invalid);
+        }
+        let total_len = Self::header_len(signers.len()) + message.len();
+        if !fits_format(format, message, total_len <= MAX_LEN_LEDGER) {
+            return Err(SdkError::Invalid);
+        }
+        Ok(Self {
+            application_domain,
+            format,
+            signers,
+            message,
+        })
+    }
+
+    pub fn application_domain(&self) -> &[u8; 32] {
+        &self.application_domain
+    }
+
+    pub fn format(&self) -> MessageFormat {
+        self.format
+    }
+
+    pub fn signers(&self) -> &'a [Address] {
+        self.signers
+    }
+
+    pub fn message(&self) -> &'a [u8] {
+        self.message
+    }
+
+    fn header_len(num_signers: usize) -> usize {
+        FIXED_HEADER_LEN + num_signers * 32
+    }
+
+    /// Number of bytes `serialize` writes.
+    pub fn serialized_len(&self) -> usize {
+        Self::header_len(self.signers.len()) + self.message.len()
+    }
+
+    /// Writes the signable bytes into `buffer`.
+    pub fn serialize<'buf>(&self, buffer: &'buf mut [u8]) -> Result<&'buf [u8]> {
+        let len = self.serialized_len();
+        if buffer.len() < len {
+            return Err(SdkError::Serialize);
+        }
+
+        let mut pos = 0;
+        let mut put = |bytes: &[u8]| {
+            buffer[pos..pos + bytes.len()].copy_from_slice(bytes);
+            pos += bytes.len();
+        };
+        put(SIGNING_DOMAIN);
+        put(&[HEADER_VERSION]);
+        put(&self.application_domain);
+        put(&[self.format as u8]);
+        put(&[self.signers.len() as u8]);
+        for signer in self.signers.iter() {
+            put(signer.as_ref());
+        }
+        put(&(self.message.len() as u16).to_le_bytes());
+        put(self.message);
+
+        Ok(&buffer[..len])
+    }
+
+    /// Parses signable bytes produced by `serialize`.
+    pub fn deserialize(data: &'a [u8]) -> Result<Self> {
+        if data.len() < FIXED_HEADER_LEN || !data.starts_with(SIGNING_DOMAIN) {
+            return Err(SdkError::Deserialize);
+        }
+        let mut pos = SIGNING_DOMAIN.len();
+        if data[pos] != HEADER_VERSION {
+            return Err(SdkError::Unsupported);
+        }
+        pos += 1;
+
+        let mut application_domain = [0u8; 32];
+        application_domain.copy_from_slice(&data[pos..pos + 32]);
+        pos += 32;
+
+        let format = MessageFormat::try_from(data[pos])?;
+        pos += 1;
+
+        let num_signers = data[pos] as usize;
+        pos += 1;
+
+        let signers_len = num_signers * 32;
+        if data.len() < pos + signers_len + 2 {
+            return Err(SdkError::Deserialize);
+        }
+        let signer_bytes = &data[pos..pos + signers_len];
+        // SAFETY: Address is a transparent newtype around [u8; 32] with alignment 1,
+        // and signer_bytes holds exactly num_signers * 32 bytes.
+        let signers: &[Address] = unsafe {
+            core::slice::from_raw_parts(signer_bytes.as_ptr() as *const Address, num_signers)
+        };
+        pos += signers_len;
+
+        let message_len = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+        pos += 2;
+        if data.len() != pos + message_len {
+            return Err(SdkError::Deserialize);
+        }
+
+        Self::new_with_format(application_domain, format, signers, &data[pos..])
+    }
+
+    /// Signs the message with `keypair`. `buffer` must hold `serialized_len()` bytes.
+    pub fn sign(&self, keypair: &Keypair, buffer: &mut [u8]) -> Result<[u8; 64]> {
+        if !self.signers.contains(keypair.public_key()) {
+            return Err(SdkError::Invalid);
+        }
+        let bytes = self.serialize(buffer)?;
+        Ok(keypair.sign_message(bytes, None))
+    }
+
+    /// Checks `signature` from `signer`, who must be listed in the header.
+    pub fn verify(
+        &self,
+        signer: &Address,
+        signature: &[u8; 64],
+        buffer: &mut [u8],
+    ) -> Result<bool> {
+        if !self.signers.contains(signer) {
+            return Ok(false);
+        }
+        let bytes = self.serialize(buffer)?;
+        Ok(signer.verify_signature(bytes, signature))
+    }
+}
+
+/// Off-chain message in the layout the Solana CLI and `solana-offchain-message`
+/// crate use: `signing domain | version | format | length (u16 LE) | body`,
+/// without application domain or signers. Signatures from
+/// `solana sign-offchain-message` verify against this layout.
+#[derive(Debug, Clone)]
+pub struct LegacyOffchainMessage<'a> {
+    format: MessageFormat,
+    message: &'a [u8],
+}
+
+impl<'a> LegacyOffchainMessage<'a> {
+    /// Builds a message, picking the most restrictive format the body fits in.
+    pub fn new(message: &'a [u8]) -> Result<Self> {
+        let format = pick_format(message, message.len() <= LEGACY_MAX_LEN_LEDGER);
+        Self::new_with_format(format, message)
+    }
+
+    /// Builds a message with an explicit format, validating the body against it.
+    pub fn new_with_format(format: MessageFormat, message: &'a [u8]) -> Result<Self> {
+        if message.is_empty()
+            || message.len() > LEGACY_MAX_LEN
+            || !fits_format(format, message, message.len() <= LEGACY_MAX_LEN_LEDGER)
+        {
+            return Err(SdkError::Invalid);
+        }
+        Ok(Self { format, message })
+    }
+
+    pub fn format(&self) -> MessageFormat {
+        self.format
+    }
+
+    pub fn message(&self) -> &'a [u8] {
+        self.message
+    }
+
+    /// Number of bytes `serialize` writes.
+    pub fn serialized_len(&self) -> usize {
+        LEGACY_HEADER_LEN + self.message.len()
+    }
+
+    /// Writes the signable bytes into `buffer`.
+    pub fn serialize<'buf>(&self, buffer: &'buf mut [u8]) -> Result<&'buf [u8]> {
+        let len = self.serialized_len();
+        let out = buffer.get_mut(..len).ok_or(SdkError::Serialize)?;
+        let (header, body) = out.split_at_mut(LEGACY_HEADER_LEN);
+        header[..SIGNING_DOMAIN.len()].copy_from_slice(SIGNING_DOMAIN);
+        header[SIGNING_DOMAIN.len()] = HEADER_VERSION;
+        header[SIGNING_DOMAIN.len() + 1] = self.format as u8;
+        header[SIGNING_DOMAIN.len() + 2..]
+            .copy_from_slice(&(self.message.len() as u16).to_le_bytes());
+        body.copy_from_slice(self.message);
+        Ok(out)
+    }
+
+    /// Parses signable bytes produced by `serialize` or the Solana CLI.
+    pub fn deserialize(data: &'a [u8]) -> Result<Self> {
+        if data.len() <= LEGACY_HEADER_LEN || !data.starts_with(SIGNING_DOMAIN) {
+            return Err(SdkError::Deserialize);
+        }
+        let header = &data[SIGNING_DOMAIN.len()..LEGACY_HEADER_LEN];
+        if header[0] != HEADER_VERSION {
+            return Err(SdkError::Unsupported);
+        }
+        let format = MessageFormat::try_from(header[1])?;
+        let message_len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        if data.len() != LEGACY_HEADER_LEN + message_len {
+            return Err(SdkError::Deserialize);
+        }
+        Self::new_with_format(format, &data[LEGACY_HEADER_LEN..])
+    }
+
+    /// Signs the message with `keypair`. `buffer` must hold `serialized_len()` bytes.
+    pub fn sign(&self, keypair: &Keypair, buffer: &mut [u8]) -> Result<[u8; 64]> {
+        let bytes = self.serialize(buffer)?;
+        Ok(keypair.sign_message(bytes, None))
+    }
+
+    /// Checks `signature` from `signer` over the serialized message.
+    pub fn verify(
+        &self,
+        signer: &Address,
+        signature: &[u8; 64],
+        buffer: &mut [u8],
+    ) -> Result<bool> {
+        let bytes = self.serialize(buffer)?;
+        Ok(signer.verify_signature(bytes, signature))
+    }
+}
+
+/// Most restrictive format `message` fits in.
+fn pick_format(message: &[u8], fits_ledger: bool) -> MessageFormat {
+    if is_printable_ascii(message) && fits_ledger {
+        MessageFormat::RestrictedAscii
+    } else if fits_ledger {
+        MessageFormat::LimitedUtf8
+    } else {
+        MessageFormat::ExtendedUtf8
+    }
+}
+
+/// Whether `message` may be declared as `format`.
+fn fits_format(format: MessageFormat, message: &[u8], fits_ledger: bool) -> bool {
+    match format {
+        MessageFormat::RestrictedAscii => is_printable_ascii(message) && fits_ledger,
+        MessageFormat::LimitedUtf8 => core::str::from_utf8(message).is_ok() && fits_ledger,
+        MessageFormat::ExtendedUtf8 => core::str::from_utf8(message).is_ok(),
+    }
+}
+
+fn is_printable_ascii(data: &[u8]) -> bool {
+    data.iter().all(|&c| (0x20..=0x7e).contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    // output of `solana sign-offchain-message` with the keypair of seed [7; 32]
+    const CLI_SIGNER: &str = "GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB";
+    const CLI_ASCII: [u8; 32] = [
+        255, 115, 111, 108, 97, 110, 97, 32, 111, 102, 102, 99, 104, 97, 105, 110, 0, 0, 12, 0, 84,
+        101, 115, 116, 32, 77, 101, 115, 115, 97, 103, 101,
+    ];
+    const CLI_ASCII_SIGNATURE: [u8; 64] = [
+        86, 189, 217, 4, 168, 241, 203, 246, 16, 47, 107, 174, 242, 243, 211, 6, 16, 132, 214, 120,
+        189, 151, 72, 45, 180, 69, 47, 248, 85, 196, 212, 253, 24, 69, 5, 1, 214, 230, 222, 69, 34,
+        94, 169, 36, 55, 169, 163, 122, 204, 125, 43, 245, 112, 95, 60, 66, 98, 6, 227, 42, 134,
+        137, 24, 13,
+    ];
+    const CLI_UTF8: [u8; 55] = [
+        255, 115, 111, 108, 97, 110, 97, 32, 111, 102, 102, 99, 104, 97, 105, 110, 0, 1, 35, 0,
+        208, 162, 208, 181, 209, 129, 209, 130, 208, 190, 208, 178, 208, 190, 208, 181, 32, 209,
+        129, 208, 190, 208, 190, 208, 177, 209, 137, 208, 181, 208, 189, 208, 184, 208, 181,
+    ];
+    const CLI_UTF8_SIGNATURE: [u8; 64] = [
+        209, 156, 146, 120, 68, 162, 151, 247, 238, 60, 37, 52, 119, 8, 42, 48, 253, 4, 145, 124,
+        62, 71, 203, 63, 17, 129, 163, 7, 139, 234, 5, 5, 185, 10, 51, 101, 39, 222, 168, 107, 173,
+        217, 17, 88, 239, 41, 165, 112, 236, 126, 202, 79, 16, 109, 128, 76, 252, 227, 79, 167, 55,
+        5, 147, 2,
+    ];
+
+    fn cli_keypair() -> Keypair {
+        let keypair = Keypair::new_from_seed([7; 32]);
+        assert_eq!(
+            keypair.public_key(),
+            &CLI_SIGNER.parse::<Address>().unwrap()
+        );
+        keypair
+    }
+
+    #[test]
+    fn legacy_matches_cli_vectors() {
+        let keypair = cli_keypair();
+        let vectors: [(&str, &[u8], MessageFormat, &[u8; 64]); 2] = [
+            (
+                "Test Message",
+                &CLI_ASCII,
+                MessageFormat::RestrictedAscii,
+                &CLI_ASCII_SIGNATURE,
+            ),
+            (
+                "Тестовое сообщение",
+                &CLI_UTF8,
+                MessageFormat::LimitedUtf8,
+                &CLI_UTF8_SIGNATURE,
+            ),
+        ];
+        for (text, serialized, format, signature) in vectors {
+            let message = LegacyOffchainMessage::new(text.as_bytes()).unwrap();
+            assert_eq!(message.format(), format);
+            let mut buffer = [0u8; 64];
+            assert_eq!(message.serialize(&mut buffer).unwrap(), serialized);
+            assert_eq!(&message.sign(&keypair, &mut buffer).unwrap(), signature);
+            assert!(message
+                .verify(keypair.public_key(), signature, &mut buffer)
+                .unwrap());
+
+            let parsed = LegacyOffchainMessage::deserialize(serialized).unwrap();
+            assert_eq!(parsed.format(), format);
+            assert_eq!(parsed.message(), text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn legacy_picks_extended_past_ledger_limit() {
+        let body = vec![b'a'; LEGACY_MAX_LEN_LEDGER + 1];
+        let message = LegacyOffchainMessage::new(&body).unwrap();
+        assert_eq!(message.format(), MessageFormat::ExtendedUtf8);
+        let body = vec![b'a'; LEGACY_MAX_LEN_LEDGER];
+        let message = LegacyOffchainMessage::new(&body).unwrap();
+        assert_eq!(message.format(), MessageFormat::RestrictedAscii);
+        let body = vec![b'a'; LEGACY_MAX_LEN + 1];
+        assert!(LegacyOffchainMessage::new(&body).is_err());
+    }
+
+    #[test]
+    fn serializes_header_layout() {
+        let keypair = cli_keypair();
+        let signers = [*keypair.public_key(), Address::new([9; 32])];
+        let message = OffchainMessage::new([1; 32], &signers, b"Test Message").unwrap();
+        let mut buffer = [0u8; 256];
+        let bytes = message.serialize(&mut buffer).unwrap();
+
+        assert_eq!(bytes.len(), message.serialized_len());
+        assert_eq!(&bytes[..16], SIGNING_DOMAIN);
+        assert_eq!(bytes[16], HEADER_VERSION);
+        assert_eq!(&bytes[17..49], &[1; 32]);
+        assert_eq!(bytes[49], MessageFormat::RestrictedAscii as u8);
+        assert_eq!(bytes[50], 2);
+        assert_eq!(&bytes[51..83], signers[0].as_ref());
+        assert_eq!(&bytes[83..115], &[9; 32]);
+        assert_eq!(&bytes[115..117], &[12, 0]);
+        assert_eq!(&bytes[117..], b"Test Message");
+    }
+
+    #[test]
+    fn round_trips_and_signs() {
+        let keypair = cli_keypair();
+        let signers = [*keypair.public_key()];
+        let text = "Тестовое сообщение";
+        let message = OffchainMessage::new([3; 32], &signers, text.as_bytes()).unwrap();
+        assert_eq!(message.format(), MessageFormat::LimitedUtf8);
+
+        let mut buffer = [0u8; 256];
+        let bytes = message.serialize(&mut buffer).unwrap();
+        let parsed = OffchainMessage::deserialize(bytes).unwrap();
+        assert_eq!(parsed.application_domain(), &[3; 32]);
+        assert_eq!(parsed.format(), MessageFormat::LimitedUtf8);
+        assert_eq!(parsed.signers(), &signers);
+        assert_eq!(parsed.message(), text.as_bytes());
+
+        let mut buffer = [0u8; 256];
+        let signature = message.sign(&keypair, &mut buffer).unwrap();
+        assert!(message
+            .verify(keypair.public_key(), &signature, &mut buffer)
+            .unwrap());
+        let mut tampered = signature;
+        tampered[0] ^= 1;
+        assert!(!message
+            .verify(keypair.public_key(), &tampered, &mut buffer)
+            .unwrap());
+
+        let outsider = Keypair::new_from_seed([8; 32]);
+        assert!(message.sign(&outsider, &mut buffer).is_err());
+        assert!(!message
+            .verify(outsider.public_key(), &signature, &mut buffer)
+            .unwrap());
+    }
+
+    #[test]
+    fn picks_format_by_total_size() {
+        let signers = [Address::new([2; 32])];
+        let header_len = FIXED_HEADER_LEN + 32;
+        let body = vec![b'a'; MAX_LEN_LEDGER - header_len];
+        let message = OffchainMessage::new([0; 32], &signers, &body).unwrap();
+        assert_eq!(message.format(), MessageFormat::RestrictedAscii);
+        let body = vec![b'a'; MAX_LEN_LEDGER - header_len + 1];
+        let message = OffchainMessage::new([0; 32], &signers, &body).unwrap();
+        assert_eq!(message.format(), MessageFormat::ExtendedUtf8);
+        assert!(OffchainMessage::new_with_format(
+            [0; 32],
+            MessageFormat::LimitedUtf8,
+            &signers,
+            &body
+        )
+        .is_err());
+        let body = vec![b'a'; MAX_LEN + 1];
+        assert!(OffchainMessage::new([0; 32], &signers, &body).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_messages() {
+        let signers = [Address::new([2; 32])];
+        assert!(OffchainMessage::new([0; 32], &[], b"hi").is_err());
+        assert!(OffchainMessage::new([0; 32], &signers, b"").is_err());
+        assert!(OffchainMessage::new_with_format(
+            [0; 32],
+            MessageFormat::RestrictedAscii,
+            &signers,
+            "é".as_bytes()
+        )
+        .is_err());
+        assert!(OffchainMessage::new_with_format(
+            [0; 32],
+            MessageFormat::LimitedUtf8,
+            &signers,
+            &[0xff, 0xfe]
+        )
+        .is_err());
+
+        let message = OffchainMessage::new([0; 32], &signers, b"hi").unwrap();
+        let mut buffer = [0u8; 128];
+        let len = message.serialize(&mut buffer).unwrap().len();
+        assert!(message.serialize(&mut [0u8; 8]).is_err());
+
+        let mut bad_version = buffer;
+        bad_version[16] = 1;
+        assert!(matches!(
+            OffchainMessage::deserialize(&bad_version[..len]),
+            Err(SdkError::Unsupported)
+        ));
+        let mut bad_format = buffer;
+        bad_format[49] = 3;
+        assert!(OffchainMessage::deserialize(&bad_format[..len]).is_err());
+        let mut bad_domain = buffer;
+        bad_domain[0] = 0;
+        assert!(OffchainMessage::deserialize(&bad_domain[..len]).is_err());
+        assert!(OffchainMessage::deserialize(&buffer[..len - 1]).is_err());
+        assert!(OffchainMessage::deserialize(&buffer[..len + 1]).is_err());
+        let mut no_signers = buffer;
+        no_signers[50] = 0;
+        assert!(OffchainMessage::deserialize(&no_signers[..len - 32]).is_err());
+
+        let mut bad_version = CLI_ASCII;
+        bad_version[16] = 1;
+        assert!(matches!(
+            LegacyOffchainMessage::deserialize(&bad_version),
+            Err(SdkError::Unsupported)
+        ));
+        let mut bad_format = CLI_ASCII;
+        bad_format[17] = 3;
+        assert!(LegacyOffchainMessage::deserialize(&bad_format).is_err());
+        let mut bad_length = CLI_ASCII;
+        bad_length[18] = 13;
+        assert!(LegacyOffchainMessage::deserialize(&bad_length).is_err());
+        assert!(LegacyOffchainMessage::deserialize(&CLI_ASCII[..31]).is_err());
+        assert!(LegacyOffchainMessage::deserialize(&CLI_ASCII[..LEGACY_HEADER_LEN]).is_err());
+        // a UTF-8 body declared as restricted ASCII
+        let mut ascii_utf8 = CLI_UTF8;
+        ascii_utf8[17] = MessageFormat::RestrictedAscii as u8;
+        assert!(LegacyOffchainMessage::deserialize(&ascii_utf8).is_err());
+    }
+}
@@ -1,13 +1,29 @@
-use core::future::Future;
+use core::{
+    fmt::{self, Write},
+    future::Future,
+};
 
-use base64::Engine;
+use base64::{display::Base64Display, engine::general_purpose::STANDARD, Engine};
 
 use crate::{
+    cluster::Cluster,
+    compute_budget::{
+        compute_budget_instruction, compute_unit_limit_with_margin, set_compute_unit_limit_data,
+        set_compute_unit_price_data, with_compute_budget, PrioritizationFee, PriorityFeeEstimator,
+        MAX_COMPUTE_UNIT_LIMIT, MAX_PRIORITIZATION_FEES,
+    },
     crypto::Address,
     hash::Hash,
+    instruction::{SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+    json::{ArrayStream, Decode, Elements, JsonStr, Kind, Value},
+    nonce::{NonceAccount, NONCE_ACCOUNT_LEN},
     signature::Signature,
-    transaction::Transaction,
-    types::{Result, SdkError},
+    token::{
+        AccountState, Mint, TokenAccount, TokenAmount, MINT_LEN, MULTISIG_LEN, TOKEN_ACCOUNT_LEN,
+    },
+    transaction::{CompiledMessage, Transaction, TransactionView, PACKET_DATA_SIZE},
+    transaction_error::TransactionError,
+    types::{Result, RpcError, RpcErrorKind, SdkError},
 };
 
 /// Transport-agnostic RPC trait. Implement this on host or embedded.
@@ -39,338 +55,3647 @@ pub trait AsyncClient {
     ) -> impl Future<Output = Result<&'a [u8]>>;
 }
 
+/// Async transport that hands over the response body in chunks as it
+/// arrives, for results too large to buffer whole.
+pub trait AsyncStreamingClient: AsyncClient {
+    /// Posts `json_body` and passes the response body to `on_chunk` in
+    /// order. Errors returned by `on_chunk` abort the request and are
+    /// returned unchanged.
+    fn post_json_streaming(
+        &self,
+        url: &str,
+        json_body: &[u8],
+        on_chunk: impl FnMut(&[u8]) -> Result<()>,
+    ) -> impl Future<Output = Result<()>>;
+}
+
+/// JSON-RPC 2.0 response envelope.
+#[derive(Debug, Clone, Copy)]
+pub struct RpcResponse<'a> {
+    /// Request id, when the node echoed a numeric one.
+    pub id: Option<u64>,
+    pub result: Option<Value<'a>>,
+    pub error: Option<Value<'a>>,
+}
+
+impl<'a> RpcResponse<'a> {
+    pub fn parse(body: &'a [u8]) -> Result<Self> {
+        Self::from_value(Value::parse(body)?)
+    }
+
+    /// Reads the envelope fields of an already parsed response object.
+    pub fn from_value(value: Value<'a>) -> Result<Self> {
+        let mut response = RpcResponse {
+            id: None,
+            result: None,
+            error: None,
+        };
+        for (key, value) in value.members() {
+            if key.eq_str("id") {
+                response.id = value.as_u64();
+            } else if key.eq_str("result") {
+                response.result = Some(value);
+            } else if key.eq_str("error") && !value.is_null() {
+                response.error = Some(value);
+            }
+        }
+        if response.result.is_none() && response.error.is_none() {
+            return Err(SdkError::ResponseParseError);
+        }
+        Ok(response)
+    }
+
+    /// The `result` member, or `SdkError::RpcError` if the node returned an error.
+    pub fn into_result(self) -> Result<Value<'a>> {
+        if let Some(error) = self.error {
+            return Err(SdkError::RpcError(parse_rpc_error(error)?));
+        }
+        self.result.ok_or(SdkError::ResponseParseError)
+    }
+}
+
+/// Decodes a JSON-RPC `error` object.
+pub fn parse_rpc_error(error: Value<'_>) -> Result<RpcError> {
+    let code: i64 = error.field("code")?;
+    let message: Option<JsonStr> = error.field("message")?;
+    let transaction_error: Option<TransactionError> = match error.path(&["data", "err"]) {
+        Some(err) => err.decode()?,
+        None => None,
+    };
+    let mut kind = RpcErrorKind::from_code(code);
+    if kind == RpcErrorKind::SendTransactionPreflightFailure
+        && transaction_error == Some(TransactionError::BlockhashNotFound)
+    {
+        kind = RpcErrorKind::BlockhashNotFound;
+    }
+    Ok(RpcError {
+        code,
+        kind,
+        message: message.map(|m| m.to_heapless()).unwrap_or_default(),
+        transaction_error,
+    })
+}
+
+/// Result shaped as `{"context":{"slot":..},"value":..}`.
+#[derive(Debug, Clone, Copy)]
+pub struct WithContext<T> {
+    pub slot: u64,
+    pub value: T,
+}
+
+impl<'a> WithContext<Value<'a>> {
+    pub fn parse(result: Value<'a>) -> Result<Self> {
+        Ok(WithContext {
+            slot: result
+                .path(&["context", "slot"])
+                .and_then(|slot| slot.as_u64())
+                .ok_or(SdkError::ResponseParseError)?,
+            value: result.get("value").ok_or(SdkError::ResponseParseError)?,
+        })
+    }
+}
+
+impl<'a, T: Decode<'a>> Decode<'a> for WithContext<T> {
+    fn decode(value: Value<'a>) -> Result<Self> {
+        let result = WithContext::parse(value)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: result.value.decode()?,
+        })
+    }
+}
+
+/// Storage a `JsonRequest` writes its body into.
+trait RequestBody: fmt::Write {
+    fn bytes(&self) -> &[u8];
+}
+
+impl<const N: usize> RequestBody for heapless::String<N> {
+    fn bytes(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Front of a caller's buffer; the rest is left for the response.
+struct BufferBody<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for BufferBody<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl RequestBody for BufferBody<'_> {
+    fn bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
+
+/// JSON-RPC request body.
+struct JsonRequest<B> {
+    body: B,
+    first: bool,
+}
+
+/// Request written into a fixed-size buffer.
+type Request<const N: usize> = JsonRequest<heapless::String<N>>;
+
+impl<const N: usize> Request<N> {
+    fn new(method: &str) -> Result<Self> {
+        Self::with_id(method, 1)
+    }
+
+    fn with_id(method: &str, id: u32) -> Result<Self> {
+        JsonRequest::start(heapless::String::new(), method, id)
+    }
+}
+
+impl<'b> JsonRequest<BufferBody<'b>> {
+    /// Request written to the front of `buffer`.
+    fn in_buffer(buffer: &'b mut [u8], method: &str) -> Result<Self> {
+        JsonRequest::start(BufferBody { buffer, len: 0 }, method, 1)
+    }
+
+    /// Finishes the body and splits it from the rest of the buffer.
+    fn split(mut self) -> Result<(&'b [u8], &'b mut [u8])> {
+        self.finish()?;
+        let BufferBody { buffer, len } = self.body;
+        let (body, rest) = buffer.split_at_mut(len);
+        Ok((body, rest))
+    }
+}
+
+impl<B: RequestBody> JsonRequest<B> {
+    fn start(body: B, method: &str, id: u32) -> Result<Self> {
+        let mut request = JsonRequest { body, first: true };
+        request.write(format_args!(
+            r#"{{"jsonrpc":"2.0","id":{id},"method":"{method}","params":["#
+        ))?;
+        Ok(request)
+    }
+
+    fn write(&mut self, args: fmt::Arguments) -> Result<()> {
+        self.body.write_fmt(args).map_err(|_| SdkError::Serialize)
+    }
+
+    fn separator(&mut self) -> Result<()> {
+        if !core::mem::replace(&mut self.first, false) {
+            self.write(format_args!(","))?;
+        }
+        Ok(())
+    }
+
+    /// Appends a positional parameter written as a JSON string.
+    fn string(&mut self, value: impl fmt::Display) -> Result<&mut Self> {
+        self.separator()?;
+        self.write(format_args!(r#""{value}""#))?;
+        Ok(self)
+    }
+
+    /// Appends a positional parameter written verbatim, e.g. a number.
+    fn raw(&mut self, value: impl fmt::Display) -> Result<&mut Self> {
+        self.separator()?;
+        self.write(format_args!("{value}"))?;
+        Ok(self)
+    }
+
+    /// Opens the trailing configuration object.
+    fn begin_config(&mut self) -> Result<&mut Self> {
+        self.separator()?;
+        self.write(format_args!("{{"))?;
+        self.first = true;
+        Ok(self)
+    }
+
+    /// Adds `"key":value` to the configuration object; strings must be quoted.
+    fn entry(&mut self, key: &str, value: impl fmt::Display) -> Result<&mut Self> {
+        self.separator()?;
+        self.write(format_args!(r#""{key}":{value}"#))?;
+        Ok(self)
+    }
+
+    /// Opens an object nested under `key` in the configuration object.
+    fn begin_object(&mut self, key: &str) -> Result<&mut Self> {
+        self.separator()?;
+        self.write(format_args!(r#""{key}":{{"#))?;
+        self.first = true;
+        Ok(self)
+    }
+
+    /// Closes the innermost object.
+    fn end_object(&mut self) -> Result<&mut Self> {
+        self.write(format_args!("}}"))?;
+        self.first = false;
+        Ok(self)
+    }
+
+    fn finish(&mut self) -> Result<&[u8]> {
+        self.write(format_args!("]}}"))?;
+        Ok(self.body.bytes())
+    }
+}
+
+/// Writes a slice as a JSON array of strings.
+struct StringArray<'a, T>(&'a [T]);
+
+impl<T: fmt::Display> fmt::Display for StringArray<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, value) in self.0.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, r#"{separator}"{value}""#)?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// How far along the cluster's confirmation a query result must be.
+///
+/// Ordered from least to most final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Commitment {
     Processed,
     Confirmed,
     Finalized,
 }
 
-/// Thin helper struct parameterized by an RpcClient impl.
-pub struct RpcClient<'a, C> {
-    url: &'a str,
-    commitment: Commitment,
-    client: C,
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
 }
 
-impl<'a, C> RpcClient<'a, C> {
-    /// Creates a new `Connection` with a **sync-only** client.
-    pub fn new_sync(url: &'a str, commitment: Commitment, client: C) -> Self
-    where
-        C: SyncClient,
-    {
-        Self {
-            url,
-            commitment,
-            client,
-        }
+impl Decode<'_> for Commitment {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let value = JsonStr::decode(value)?;
+        [
+            Commitment::Processed,
+            Commitment::Confirmed,
+            Commitment::Finalized,
+        ]
+        .into_iter()
+        .find(|commitment| value.eq_str(commitment.as_str()))
+        .ok_or(SdkError::ResponseParseError)
     }
+}
 
-    /// Creates a new `Connection` with an **async-only** client.
-    pub fn new_async(url: &'a str, commitment: Commitment, client: C) -> Self
-    where
-        C: AsyncClient,
-    {
-        Self {
-            url,
-            commitment,
-            client,
+impl fmt::Display for Commitment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Byte range of account data to return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataSlice {
+    pub offset: usize,
+    pub length: usize,
+}
+
+/// How the node encodes account data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AccountEncoding {
+    #[default]
+    Base64,
+    /// JSON for programs the node can parse, base64 for the rest. Token
+    /// accounts, mints and nonce accounts are read into `Account::parsed`
+    /// without needing a data buffer.
+    JsonParsed,
+}
+
+impl AccountEncoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountEncoding::Base64 => "base64",
+            AccountEncoding::JsonParsed => "jsonParsed",
         }
     }
+}
 
-    fn extract_blockhash(json: &[u8]) -> Result<Hash> {
-        // Step 1: Find the `"blockhash":"` prefix
-        let blockhash_prefix = br#""blockhash":""#;
-        let start = json
-            .windows(blockhash_prefix.len())
-            .position(|w| w == blockhash_prefix)
-            .ok_or(SdkError::ResponseParseError)?
-            + blockhash_prefix.len();
+/// Options for `get_account_info` and `get_multiple_accounts`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccountInfoConfig {
+    /// Overrides the client's commitment for this call.
+    pub commitment: Option<Commitment>,
+    pub encoding: AccountEncoding,
+    /// Only honoured for base64; nodes reject it with `JsonParsed`.
+    pub data_slice: Option<DataSlice>,
+    /// Fails with `MinContextSlotNotReached` if the node is behind this slot.
+    pub min_context_slot: Option<u64>,
+}
 
-        // Step 2: Extract the blockhash (ends with `"`)
-        let end = json[start..]
-            .iter()
-            .position(|&c| c == b'"')
-            .ok_or(SdkError::ResponseParseError)?
-            + start;
+/// On-chain account as returned by `getAccountInfo`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Account<'a> {
+    pub lamports: u64,
+    pub owner: Address,
+    /// Decoded data, or only the requested slice of it; empty when the
+    /// node returned it as parsed JSON.
+    pub data: &'a [u8],
+    pub executable: bool,
+    pub rent_epoch: u64,
+    /// Full size of the account data, even when sliced.
+    pub space: u64,
+    /// Typed view of token accounts, mints and nonce accounts, read from
+    /// parsed JSON or from the raw data.
+    pub parsed: Option<ParsedAccount>,
+}
 
-        // Step 3: Decode the blockhash
-        let mut hash_bytes = [0u8; 32];
-        five8::decode_32(&json[start..end], &mut hash_bytes)
-            .map_err(|_| SdkError::ResponseParseError)?;
-        Ok(Hash::from(hash_bytes))
+impl<'a> Account<'a> {
+    /// Decodes an account object, writing base64-encoded data into
+    /// `data_buffer`.
+    pub fn parse(value: Value<'_>, data_buffer: &'a mut [u8]) -> Result<Self> {
+        let len = decode_account_data(value, data_buffer)?;
+        Self::from_parts(value, &data_buffer[..len])
     }
 
-    fn extract_signature(json: &[u8]) -> Result<Signature> {
-        let result_prefix = br#""result":""#;
-        let start = json
-            .windows(result_prefix.len())
-            .position(|w| w == result_prefix)
-            .ok_or(SdkError::ResponseParseError)?
-            + result_prefix.len();
-        let end = json[start..]
-            .iter()
-            .position(|&c| c == b'"')
-            .ok_or(SdkError::ResponseParseError)?
-            + start;
-        let mut sig_bytes = [0u8; 64];
-        five8::decode_64(&json[start..end], &mut sig_bytes)
-            .map_err(|_| SdkError::ResponseParseError)?;
-        Ok(Signature::from(sig_bytes))
+    fn from_parts(value: Value<'_>, data: &'a [u8]) -> Result<Self> {
+        let owner = value.field("owner")?;
+        // older nodes omit `space`
+        let space = value
+            .field::<Option<u64>>("space")?
+            .unwrap_or(data.len() as u64);
+        let parsed = match value.get("data") {
+            Some(json) if json.kind() == Kind::Object => ParsedAccount::from_json(json)?,
+            // a `dataSlice` prefix could pass for a different layout
+            _ if data.len() as u64 != space => None,
+            _ => ParsedAccount::unpack(&owner, data),
+        };
+        Ok(Account {
+            lamports: value.field("lamports")?,
+            owner,
+            data,
+            executable: value.field("executable")?,
+            rent_epoch: value.field("rentEpoch")?,
+            space,
+            parsed,
+        })
     }
+}
 
-    fn extract_data<'buf>(json: &[u8], data_buffer: &'buf mut [u8]) -> Result<&'buf [u8]> {
-        let result_prefix = br#""value":{"data":[""#;
-        let start = json
-            .windows(result_prefix.len())
-            .position(|w| w == result_prefix)
-            .ok_or(SdkError::ResponseParseError)?
-            + result_prefix.len();
-        let end = json[start..]
-            .iter()
-            .position(|&c| c == b'"')
+/// Account types with a typed view, decoded from `jsonParsed` data or from
+/// the binary layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParsedAccount {
+    TokenAccount(TokenAccount),
+    Mint(Mint),
+    Nonce(NonceAccount),
+}
+
+impl ParsedAccount {
+    /// Decodes raw data of an account owned by `owner`; `None` for other
+    /// programs or data that does not match the layout.
+    pub fn unpack(owner: &Address, data: &[u8]) -> Option<Self> {
+        let is_mint = if owner == &TOKEN_PROGRAM_ID {
+            match data.len() {
+                MINT_LEN => true,
+                TOKEN_ACCOUNT_LEN => false,
+                _ => return None,
+            }
+        } else if owner == &TOKEN_2022_PROGRAM_ID {
+            // Token-2022 pads extended mints to the account size and tags
+            // both kinds with a type byte after the base layout
+            match (data.len(), data.get(TOKEN_ACCOUNT_LEN)) {
+                (MINT_LEN, _) => true,
+                (TOKEN_ACCOUNT_LEN, _) => false,
+                (MULTISIG_LEN, _) => return None,
+                (_, Some(1)) => true,
+                (_, Some(2)) => false,
+                _ => return None,
+            }
+        } else if owner == &SYSTEM_PROGRAM_ID && data.len() == NONCE_ACCOUNT_LEN {
+            return NonceAccount::unpack(data).ok().map(ParsedAccount::Nonce);
+        } else {
+            return None;
+        };
+        match is_mint {
+            true => Mint::unpack(data).ok().map(ParsedAccount::Mint),
+            false => TokenAccount::unpack(data)
+                .ok()
+                .map(ParsedAccount::TokenAccount),
+        }
+    }
+
+    /// Decodes `{"program":..,"parsed":{"type":..,"info":..}}`; `None` for
+    /// programs and account types without a typed view.
+    pub fn from_json(value: Value<'_>) -> Result<Option<Self>> {
+        let program: JsonStr = value.field("program")?;
+        let kind: JsonStr = value
+            .path(&["parsed", "type"])
             .ok_or(SdkError::ResponseParseError)?
-            + start;
-        let data = &json[start..end];
-        let len = base64::engine::general_purpose::STANDARD
-            .decode_slice(data, data_buffer)
-            .map_err(|_| SdkError::ResponseParseError)?;
-        Ok(&data_buffer[..len])
+            .decode()?;
+        let Some(info) = value.path(&["parsed", "info"]) else {
+            return Ok(None);
+        };
+        let is_token = program.eq_str("spl-token") || program.eq_str("spl-token-2022");
+        if is_token && kind.eq_str("account") {
+            Ok(Some(ParsedAccount::TokenAccount(parsed_token_account(
+                info,
+            )?)))
+        } else if is_token && kind.eq_str("mint") {
+            Ok(Some(ParsedAccount::Mint(Mint {
+                mint_authority: info.field("mintAuthority")?,
+                supply: string_u64(info.get("supply"))?,
+                decimals: info.field("decimals")?,
+                is_initialized: info.field("isInitialized")?,
+                freeze_authority: info.field("freezeAuthority")?,
+            })))
+        } else if program.eq_str("nonce") && kind.eq_str("initialized") {
+            Ok(Some(ParsedAccount::Nonce(NonceAccount {
+                authority: info.field("authority")?,
+                durable_nonce: info.field("blockhash")?,
+                lamports_per_signature: string_u64(
+                    info.path(&["feeCalculator", "lamportsPerSignature"]),
+                )?,
+            })))
+        } else {
+            Ok(None)
+        }
     }
 }
 
-impl<'a, C: AsyncClient> RpcClient<'a, C> {
-    pub async fn get_latest_blockhash(&self) -> Result<Hash> {
-        let json_body = match self.commitment {
-            Commitment::Processed => br#"{"jsonrpc":"2.0","id":1,"method":"getLatestBlockhash","params":[{"commitment": "processed"}]}"#,
-            Commitment::Confirmed => br#"{"jsonrpc":"2.0","id":1,"method":"getLatestBlockhash","params":[{"commitment": "confirmed"}]}"#,
-            Commitment::Finalized => br#"{"jsonrpc":"2.0","id":1,"method":"getLatestBlockhash","params":[{"commitment": "finalized"}]}"#,
-        };
-        let mut resp_buffer = [0u8; 4096];
-        let reponse = self
-            .client
-            .post_json(self.url, json_body.as_slice(), resp_buffer.as_mut_slice())
-            .await?;
-        Self::extract_blockhash(&reponse)
+/// Reads the `info` object of a parsed SPL token account.
+fn parsed_token_account(info: Value<'_>) -> Result<TokenAccount> {
+    let state: JsonStr = info.field("state")?;
+    let state = if state.eq_str("initialized") {
+        AccountState::Initialized
+    } else if state.eq_str("frozen") {
+        AccountState::Frozen
+    } else if state.eq_str("uninitialized") {
+        AccountState::Uninitialized
+    } else {
+        return Err(SdkError::ResponseParseError);
+    };
+    let is_native = match info.field::<bool>("isNative")? {
+        true => Some(info.field::<TokenAmount>("rentExemptReserve")?.amount),
+        false => None,
+    };
+    Ok(TokenAccount {
+        mint: info.field("mint")?,
+        owner: info.field("owner")?,
+        amount: info.field::<TokenAmount>("tokenAmount")?.amount,
+        delegate: info.field("delegate")?,
+        state,
+        is_native,
+        delegated_amount: info
+            .field::<Option<TokenAmount>>("delegatedAmount")?
+            .map_or(0, |amount| amount.amount),
+        close_authority: info.field("closeAuthority")?,
+    })
+}
+
+/// Reads a `u64` the node sends as a decimal string to keep its precision.
+fn string_u64(value: Option<Value<'_>>) -> Result<u64> {
+    value
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.parse().ok())
+        .ok_or(SdkError::ResponseParseError)
+}
+
+/// Decodes a `getAccountInfo` result.
+fn account_info<'buf>(
+    result: Value<'_>,
+    data_buffer: &'buf mut [u8],
+) -> Result<WithContext<Option<Account<'buf>>>> {
+    let result = WithContext::parse(result)?;
+    let value = match result.value.is_null() {
+        true => None,
+        false => Some(Account::parse(result.value, data_buffer)?),
+    };
+    Ok(WithContext {
+        slot: result.slot,
+        value,
+    })
+}
+
+/// Decodes base64 account data into `buffer`; parsed JSON data takes no space.
+fn decode_account_data(value: Value<'_>, buffer: &mut [u8]) -> Result<usize> {
+    let data = value.get("data").ok_or(SdkError::ResponseParseError)?;
+    if data.kind() == Kind::Object {
+        return Ok(0);
     }
+    let data: JsonStr = data.at(0).ok_or(SdkError::ResponseParseError)?.decode()?;
+    STANDARD
+        .decode_slice(data.raw(), buffer)
+        .map_err(|_| SdkError::ResponseParseError)
+}
 
-    pub async fn send_transaction(
+impl AccountInfoConfig {
+    fn write<const N: usize>(
         &self,
-        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
-    ) -> Result<Signature> {
-        struct KeyMetadata {
-            pub is_signer: bool,
-            pub is_writable: bool,
-        }
-
-        // get map of all accounts
-        let mut keys_meta_map: heapless::LinearMap<&Address, KeyMetadata, 35> =
-            heapless::LinearMap::new();
-        for instruction in transaction.instructions.iter() {
-            // program cannot be writable or signer so it is safe to overwrite
-            let _ = keys_meta_map.insert(
-                &instruction.program_id,
-                KeyMetadata {
-                    is_signer: false,
-                    is_writable: false,
-                },
-            );
-            for account_meta in instruction.accounts.iter() {
-                let key_meta = keys_meta_map.get_mut(account_meta.address);
-                if let Some(key_meta) = key_meta {
-                    key_meta.is_writable |= account_meta.is_writable;
-                    key_meta.is_signer |= account_meta.is_signer;
-                    continue;
-                }
-                let _ = keys_meta_map.insert(
-                    account_meta.address,
-                    KeyMetadata {
-                        is_signer: account_meta.is_signer,
-                        is_writable: account_meta.is_writable,
-                    },
-                );
-            }
+        commitment: Commitment,
+        request: &mut Request<N>,
+    ) -> Result<()> {
+        request.begin_config()?.entry(
+            "commitment",
+            format_args!(r#""{}""#, self.commitment.unwrap_or(commitment)),
+        )?;
+        request.entry("encoding", format_args!(r#""{}""#, self.encoding.as_str()))?;
+        if let Some(slice) = self.data_slice {
+            request.entry(
+                "dataSlice",
+                format_args!(r#"{{"offset":{},"length":{}}}"#, slice.offset, slice.length),
+            )?;
+        }
+        if let Some(slot) = self.min_context_slot {
+            request.entry("minContextSlot", slot)?;
         }
+        request.end_object()?;
+        Ok(())
+    }
+}
 
-        let mut writable_signer_keys: heapless::Vec<&Address, 35> = heapless::Vec::new();
-        let mut readonly_signer_keys: heapless::Vec<&Address, 35> = heapless::Vec::new();
-        let mut writable_non_signer_keys: heapless::Vec<&Address, 35> = heapless::Vec::new();
-        let mut readonly_non_signer_keys: heapless::Vec<&Address, 35> = heapless::Vec::new();
-        let mut static_account_keys: heapless::Vec<&Address, 35> = heapless::Vec::new();
+/// Most addresses a single `getMultipleAccounts` call accepts.
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
 
-        for (key, meta) in keys_meta_map.iter() {
-            if meta.is_writable {
-                if meta.is_signer {
-                    let _ = writable_signer_keys.push(*key);
-                } else {
-                    let _ = writable_non_signer_keys.push(*key).ok();
-                }
-            } else {
-                if meta.is_signer {
-                    let _ = readonly_signer_keys.push(*key).ok();
-                } else {
-                    let _ = readonly_non_signer_keys.push(*key).ok();
+/// Where `get_multiple_accounts` writes decoded account data.
+pub enum DataBuffers<'a, 'buf> {
+    /// One buffer shared by all accounts, filled front to back.
+    Arena(&'buf mut [u8]),
+    /// One buffer per requested address, in request order.
+    PerAccount(&'a mut [&'buf mut [u8]]),
+}
+
+/// Accounts returned by `get_multiple_accounts`, in request order; `None`
+/// for addresses that do not exist.
+pub struct MultipleAccounts<'r, 'a, 'buf> {
+    elements: Elements<'r>,
+    buffers: DataBuffers<'a, 'buf>,
+    index: usize,
+}
+
+impl<'buf> Iterator for MultipleAccounts<'_, '_, 'buf> {
+    type Item = Result<Option<Account<'buf>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.elements.next()?;
+        let index = self.index;
+        self.index += 1;
+        if value.is_null() {
+            return Some(Ok(None));
+        }
+        let buffer = match &mut self.buffers {
+            DataBuffers::Arena(arena) => core::mem::take(arena),
+            DataBuffers::PerAccount(buffers) => match buffers.get_mut(index) {
+                Some(buffer) => core::mem::take(buffer),
+                None => return Some(Err(SdkError::ResponseParseError)),
+            },
+        };
+        let len = match decode_account_data(value, buffer) {
+            Ok(len) => len,
+            Err(e) => {
+                // keep the space for the accounts that follow
+                match &mut self.buffers {
+                    DataBuffers::Arena(arena) => *arena = buffer,
+                    DataBuffers::PerAccount(buffers) => buffers[index] = buffer,
                 }
+                return Some(Err(e));
             }
+        };
+        let (data, rest) = buffer.split_at_mut(len);
+        if let DataBuffers::Arena(arena) = &mut self.buffers {
+            *arena = rest;
         }
-        let num_required_signatures: u8 =
-            (writable_signer_keys.len() + readonly_signer_keys.len()) as u8;
-        let num_readonly_signed_accounts: u8 = readonly_signer_keys.len() as u8;
-        let num_readonly_unsigned_accounts: u8 = readonly_non_signer_keys.len() as u8;
-        static_account_keys.extend(writable_signer_keys.into_iter());
-        static_account_keys.extend(readonly_signer_keys.into_iter());
-        static_account_keys.extend(writable_non_signer_keys.into_iter());
-        static_account_keys.extend(readonly_non_signer_keys.into_iter());
+        Some(Account::from_parts(value, data).map(Some))
+    }
+}
 
-        // build message
-        let mut msg_buffer: heapless::Vec<u8, 1200> = heapless::Vec::new(); // can be smaller
+/// Most filters a `getProgramAccounts` request may carry.
+pub const MAX_PROGRAM_ACCOUNTS_FILTERS: usize = 4;
+/// Longest `Memcmp` pattern nodes accept.
+pub const MAX_MEMCMP_BYTES: usize = 128;
 
-        // SAFETY: msg_buffer is empty and has enough space
-        unsafe {
-            msg_buffer.push_unchecked(num_required_signatures);
-            msg_buffer.push_unchecked(num_readonly_signed_accounts);
-            msg_buffer.push_unchecked(num_readonly_unsigned_accounts);
-            // number of accounts is less then 128, so 1 byte is enough
-            msg_buffer.push_unchecked(static_account_keys.len() as u8);
-        };
+/// Server-side filter for `get_program_accounts`; an account must match all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgramAccountsFilter<'a> {
+    /// Account data is exactly this many bytes.
+    DataSize(u64),
+    /// Account data contains `bytes` at `offset`.
+    Memcmp { offset: usize, bytes: &'a [u8] },
+}
 
-        for key in static_account_keys.iter() {
-            // Result is always Ok because msg_buffer has enough space
-            let _ = msg_buffer.extend_from_slice(key.as_ref());
+impl fmt::Display for ProgramAccountsFilter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProgramAccountsFilter::DataSize(size) => write!(f, r#"{{"dataSize":{size}}}"#),
+            ProgramAccountsFilter::Memcmp { offset, bytes } => write!(
+                f,
+                r#"{{"memcmp":{{"offset":{offset},"bytes":"{}","encoding":"base64"}}}}"#,
+                Base64Display::new(bytes, &STANDARD)
+            ),
         }
+    }
+}
 
-        // Result is always Ok because msg_buffer has enough space
-        let _ = msg_buffer.extend_from_slice(transaction.recent_blockhash.as_ref());
-
-        // number of instructions is less then 128, so 1 byte is enough
-        // SAFETY: msg_buffer has enough space
-        unsafe {
-            msg_buffer.push_unchecked(transaction.instructions.len() as u8);
-        };
+/// Options for `get_program_accounts`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProgramAccountsConfig<'a> {
+    /// Overrides the client's commitment for this call.
+    pub commitment: Option<Commitment>,
+    pub data_slice: Option<DataSlice>,
+    pub filters: &'a [ProgramAccountsFilter<'a>],
+    /// Asks for the result wrapped with the slot it was read at.
+    pub with_context: bool,
+    /// Fails with `MinContextSlotNotReached` if the node is behind this slot.
+    pub min_context_slot: Option<u64>,
+}
 
-        let mut compiled_instruction: heapless::Vec<u8, 1100> = heapless::Vec::new();
-        for instruction in transaction.instructions.iter() {
-            // find position of program_id in static_account_keys
-            let position = static_account_keys
-                .iter()
-                .position(|k| k == &instruction.program_id)
-                .unwrap(); // always exists
-
-            compiled_instruction
-                .push(position as u8)
-                .map_err(|_| SdkError::TransactionTooLarge)?;
-
-            // number of accounts is less then 128, so 1 byte is enough
-            compiled_instruction
-                .push(instruction.accounts.len() as u8)
-                .map_err(|_| SdkError::TransactionTooLarge)?;
-
-            for account_meta in instruction.accounts.iter() {
-                let key = account_meta.address;
-                // find position in static_account_keys
-                let position = static_account_keys.iter().position(|k| k == &key).unwrap(); // always exists
-
-                compiled_instruction
-                    .push(position as u8)
-                    .map_err(|_| SdkError::TransactionTooLarge)?;
-            }
+/// Account and its address, from `get_program_accounts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyedAccount<'a> {
+    pub pubkey: Address,
+    pub account: Account<'a>,
+}
 
-            // size of data
-            let data_len = instruction.data.len();
-            if data_len < 128 {
-                compiled_instruction
-                    .push(data_len as u8)
-                    .map_err(|_| SdkError::TransactionTooLarge)?;
-            } else {
-                compiled_instruction
-                    .push(data_len as u8)
-                    .map_err(|_| SdkError::TransactionTooLarge)?;
-                compiled_instruction
-                    .push((data_len >> 7) as u8)
-                    .map_err(|_| SdkError::TransactionTooLarge)?;
-            }
-            compiled_instruction
-                .extend_from_slice(&instruction.data)
-                .map_err(|_| SdkError::TransactionTooLarge)?;
+impl<'a> KeyedAccount<'a> {
+    /// Decodes a `{"pubkey":..,"account":..}` element, writing the account
+    /// data into `data_buffer`.
+    pub fn parse(value: Value<'_>, data_buffer: &'a mut [u8]) -> Result<Self> {
+        Ok(KeyedAccount {
+            pubkey: value.field("pubkey")?,
+            account: Account::parse(
+                value.get("account").ok_or(SdkError::ResponseParseError)?,
+                data_buffer,
+            )?,
+        })
+    }
+}
 
-            msg_buffer
-                .extend_from_slice(&compiled_instruction)
-                .map_err(|_| SdkError::TransactionTooLarge)?;
+/// Options shared by queries that only take a commitment and minimum slot.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContextConfig {
+    /// Overrides the client's commitment for this call.
+    pub commitment: Option<Commitment>,
+    /// Fails with `MinContextSlotNotReached` if the node is behind this slot.
+    pub min_context_slot: Option<u64>,
+}
 
-            compiled_instruction.clear();
+impl ContextConfig {
+    fn write<const N: usize>(
+        &self,
+        commitment: Commitment,
+        request: &mut Request<N>,
+    ) -> Result<()> {
+        request.begin_config()?.entry(
+            "commitment",
+            format_args!(r#""{}""#, self.commitment.unwrap_or(commitment)),
+        )?;
+        if let Some(slot) = self.min_context_slot {
+            request.entry("minContextSlot", slot)?;
         }
+        request.end_object()?;
+        Ok(())
+    }
+}
 
-        // sign message
-        let mut transaction_bytes: heapless::Vec<u8, 1232> = heapless::Vec::new();
+/// Whether a fee payer can afford a message, from `check_funding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FundingReport {
+    pub balance: u64,
+    pub fee: u64,
+    /// Rent-exempt minimum of every account the message creates.
+    pub rent: u64,
+    /// Lamports the fee payer sends through System program transfers and
+    /// account creations, counting each created account at no less than
+    /// its rent-exempt minimum.
+    pub transfers: u64,
+    /// Lamports missing from the balance; zero when affordable.
+    pub shortfall: u64,
+}
+
+impl FundingReport {
+    pub fn is_funded(&self) -> bool {
+        self.shortfall == 0
+    }
+}
 
-        // SAFETY: sig_buffer is empty
-        unsafe {
-            transaction_bytes.push_unchecked(transaction.signers.len() as u8);
+/// Lamports moved by a System program instruction of a compiled message.
+struct SystemTransfer {
+    /// Whether the fee payer is the funding account.
+    from_payer: bool,
+    lamports: u64,
+    /// Data size of the account the instruction creates.
+    space: Option<u64>,
+}
+
+/// System program transfers and account creations in `message`.
+fn system_transfers<'m>(
+    message: &CompiledMessage<'m>,
+) -> impl Iterator<Item = SystemTransfer> + 'm {
+    let message = *message;
+    message.instructions().filter_map(move |instruction| {
+        if message.account_key(instruction.program_id_index as usize) != Some(&SYSTEM_PROGRAM_ID) {
+            return None;
         }
+        let data = instruction.data;
+        let u64_at = |offset: usize| -> Option<u64> {
+            Some(u64::from_le_bytes(
+                data.get(offset..offset + 8)?.try_into().ok()?,
+            ))
+        };
+        let (lamports_offset, space_offset) = match data.get(..4)? {
+            // CreateAccount { lamports, space, owner }
+            [0, 0, 0, 0] => (4, Some(12)),
+            // Transfer { lamports }
+            [2, 0, 0, 0] => (4, None),
+            // CreateAccountWithSeed { base, seed, lamports, space, owner }
+            [3, 0, 0, 0] => {
+                let seed_len = usize::try_from(u64_at(36)?).ok()?;
+                let offset = 44usize.checked_add(seed_len)?;
+                (offset, Some(offset + 8))
+            }
+            _ => return None,
+        };
+        Some(SystemTransfer {
+            from_payer: instruction.accounts.first() == Some(&0),
+            lamports: u64_at(lamports_offset)?,
+            space: match space_offset {
+                Some(offset) => Some(u64_at(offset)?),
+                None => None,
+            },
+        })
+    })
+}
+
+/// Options for `send_transaction_with_config`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SendTransactionConfig {
+    /// Skips the node's simulation before forwarding the transaction.
+    pub skip_preflight: bool,
+    /// Commitment the preflight simulation runs at; the node defaults to finalized.
+    pub preflight_commitment: Option<Commitment>,
+    /// How often the node rebroadcasts; `None` leaves it to the node.
+    pub max_retries: Option<usize>,
+    pub min_context_slot: Option<u64>,
+    /// Prepends a `SetComputeUnitPrice` instruction with this priority fee,
+    /// in micro-lamports per unit, replacing any the transaction carries.
+    pub compute_unit_price: Option<u64>,
+}
+
+/// Options for `simulate_transaction`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimulateTransactionConfig<'a> {
+    /// Verifies signatures; conflicts with `replace_recent_blockhash`.
+    pub sig_verify: bool,
+    /// Simulates against the latest blockhash instead of the transaction's.
+    pub replace_recent_blockhash: bool,
+    pub commitment: Option<Commitment>,
+    /// Accounts whose post-simulation state is returned, base64 encoded.
+    pub accounts: Option<&'a [Address]>,
+    pub inner_instructions: bool,
+    pub min_context_slot: Option<u64>,
+}
+
+/// Outcome of `simulate_transaction`. Logs are passed to the caller's sink
+/// while parsing and are not kept here.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationResult<'r> {
+    pub err: Option<TransactionError>,
+    pub units_consumed: Option<u64>,
+    pub return_data: Option<ReturnData<'r>>,
+    /// `accounts` array when requested; decode entries with `Account::parse`.
+    pub accounts: Option<Value<'r>>,
+    /// Raw `innerInstructions` array when requested.
+    pub inner_instructions: Option<Value<'r>>,
+}
 
-        for signer in transaction.signers.iter() {
-            let signature = signer.sign_message(msg_buffer.as_slice(), None);
-            transaction_bytes
-                .extend_from_slice(signature.as_ref())
-                .map_err(|_| SdkError::TransactionTooLarge)?;
+/// Data a program set with `set_return_data`.
+#[derive(Debug, Clone, Copy)]
+pub struct ReturnData<'r> {
+    pub program_id: Address,
+    data: JsonStr<'r>,
+}
+
+impl ReturnData<'_> {
+    /// Decodes the base64 payload into `buffer`.
+    pub fn decode<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b [u8]> {
+        let len = STANDARD
+            .decode_slice(self.data.raw(), buffer)
+            .map_err(|_| SdkError::ResponseParseError)?;
+        Ok(&buffer[..len])
+    }
+}
+
+impl<'r> Decode<'r> for ReturnData<'r> {
+    fn decode(value: Value<'r>) -> Result<Self> {
+        Ok(ReturnData {
+            program_id: value.field("programId")?,
+            data: value
+                .get("data")
+                .and_then(|data| data.at(0))
+                .ok_or(SdkError::ResponseParseError)?
+                .decode()?,
+        })
+    }
+}
+
+/// How `send_with_compute_unit_limit` sizes the compute unit limit.
+#[derive(Debug, Clone, Copy)]
+pub struct ComputeUnitConfig {
+    /// Extra units on top of the simulated consumption, in percent.
+    pub margin_percent: u32,
+    /// Also sets a compute unit price estimated from recent fees paid for
+    /// the transaction's writable accounts; takes precedence over the send
+    /// config's `compute_unit_price`.
+    pub priority_fee: Option<PriorityFeeEstimator>,
+}
+
+impl Default for ComputeUnitConfig {
+    fn default() -> Self {
+        ComputeUnitConfig {
+            margin_percent: 10,
+            priority_fee: None,
         }
+    }
+}
+
+/// Compute budget `send_with_compute_unit_limit` attached to a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppliedComputeBudget {
+    pub unit_limit: u32,
+    /// Micro-lamports per unit, when a priority fee was requested.
+    pub unit_price: Option<u64>,
+}
 
-        transaction_bytes
-            .extend_from_slice(msg_buffer.as_slice())
-            .map_err(|_| SdkError::TransactionTooLarge)?;
+/// Most addresses `getRecentPrioritizationFees` accepts.
+pub const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
 
-        // send transaction
-        let mut json_body: heapless::Vec<u8, 4096> = heapless::Vec::new();
-        let _ = json_body.extend_from_slice(
-            br#"{"jsonrpc":"2.0","id":1,"method":"sendTransaction","params":[""#,
-        );
-        let transaction_base64_max_len = (transaction_bytes.len() * 4 / 3) + 4;
-        let current_len = json_body.len();
-        let _ = json_body.resize_default(transaction_base64_max_len + current_len);
+impl Decode<'_> for PrioritizationFee {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        Ok(PrioritizationFee {
+            slot: value.field("slot")?,
+            prioritization_fee: value.field("prioritizationFee")?,
+        })
+    }
+}
 
-        // get slice from empty space
-        let mut transaction_base64 =
-            &mut json_body[current_len..transaction_base64_max_len + current_len];
+/// Position in the current epoch, from `get_epoch_info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochInfo {
+    pub absolute_slot: u64,
+    pub block_height: u64,
+    pub epoch: u64,
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    pub transaction_count: Option<u64>,
+}
 
-        let bytes_written = base64::engine::general_purpose::STANDARD
-            .encode_slice(transaction_bytes.as_slice(), &mut transaction_base64)
-            .unwrap();
-        json_body.truncate(current_len + bytes_written);
+impl Decode<'_> for EpochInfo {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        Ok(EpochInfo {
+            absolute_slot: value.field("absoluteSlot")?,
+            block_height: value.field("blockHeight")?,
+            epoch: value.field("epoch")?,
+            slot_index: value.field("slotIndex")?,
+            slots_in_epoch: value.field("slotsInEpoch")?,
+            transaction_count: value.field("transactionCount")?,
+        })
+    }
+}
 
-        let _ = json_body.push(b'"');
-        let _ = json_body.extend_from_slice(br#",{"encoding":"base64"}]}"#);
+/// Whether a node is keeping up with the cluster, from `get_health`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeHealth {
+    Healthy,
+    /// Lagging the cluster by this many slots, when the node can tell.
+    Behind(Option<u64>),
+}
 
-        let mut resp_buffer = [0u8; 4096];
-        let response = self
-            .client
-            .post_json(self.url, json_body.as_slice(), resp_buffer.as_mut_slice())
-            .await?;
+/// Bytes of the `solana-core` version string kept in `NodeVersion`.
+pub const MAX_VERSION_LEN: usize = 24;
+
+/// Software a node runs, from `get_version`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersion {
+    /// `solana-core` version, e.g. `2.1.13`.
+    pub solana_core: heapless::String<MAX_VERSION_LEN>,
+    /// Identifier of the node's active feature set.
+    pub feature_set: Option<u32>,
+}
 
-        Self::extract_signature(&response)
+impl NodeVersion {
+    /// Major, minor and patch numbers of `solana_core`.
+    pub fn semver(&self) -> Option<(u32, u32, u32)> {
+        let core = self.solana_core.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse().ok());
+        let version = (parts.next()??, parts.next()??, parts.next()??);
+        match parts.next() {
+            None => Some(version),
+            Some(_) => None,
+        }
     }
+}
 
-    pub async fn get_data<'buf>(
-        &self,
-        address_b58: &str,
-        data_buffer: &'buf mut [u8],
-        resp_buffer: &'buf mut [u8],
-    ) -> Result<&'buf [u8]> {
-        let mut json_body: heapless::Vec<u8, 2048> = heapless::Vec::new();
-        let _ = json_body
-            .extend_from_slice(br#"{"jsonrpc":"2.0","id":1,"method":"getAccountInfo","params":[""#);
-        let _ = json_body.extend_from_slice(address_b58.as_bytes());
-        let _ = json_body.extend_from_slice(br#"",{"commitment":"#);
-        let _ = json_body.extend_from_slice(match self.commitment {
-            Commitment::Processed => br#""processed""#,
-            Commitment::Confirmed => br#""confirmed""#,
-            Commitment::Finalized => br#""finalized""#,
-        });
-        let _ = json_body.extend_from_slice(br#","encoding":"base64"}]}"#);
-        let reponse = self
-            .client
-            .post_json(self.url, json_body.as_slice(), resp_buffer)
-            .await?;
+impl Decode<'_> for NodeVersion {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        Ok(NodeVersion {
+            solana_core: value.field::<JsonStr>("solana-core")?.to_heapless(),
+            feature_set: value.field("feature-set")?,
+        })
+    }
+}
+
+/// Paging options for `get_signatures_for_address`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignaturesForAddressConfig {
+    /// Start searching backwards from this transaction.
+    pub before: Option<Signature>,
+    /// Stop at this transaction.
+    pub until: Option<Signature>,
+    /// At most 1000; the node defaults to 1000.
+    pub limit: Option<usize>,
+    /// `Confirmed` or `Finalized`; the client's commitment when `None`.
+    pub commitment: Option<Commitment>,
+    pub min_context_slot: Option<u64>,
+}
 
-        // Ok(reponse)
-        Self::extract_data(reponse, data_buffer)
+/// Transaction that referenced an address, from `get_signatures_for_address`.
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureInfo<'r> {
+    pub signature: Signature,
+    pub slot: u64,
+    pub err: Option<TransactionError>,
+    pub memo: Option<JsonStr<'r>>,
+    pub block_time: Option<i64>,
+    pub confirmation_status: Option<Commitment>,
+}
+
+impl<'r> Decode<'r> for SignatureInfo<'r> {
+    fn decode(value: Value<'r>) -> Result<Self> {
+        Ok(SignatureInfo {
+            signature: value.field("signature")?,
+            slot: value.field("slot")?,
+            err: value.field("err")?,
+            memo: value.field("memo")?,
+            block_time: value.field("blockTime")?,
+            confirmation_status: value.field("confirmationStatus")?,
+        })
+    }
+}
+
+/// Lazily decoded elements of a JSON array in a response.
+#[derive(Clone)]
+pub struct ArrayView<'r, T> {
+    elements: Elements<'r>,
+    _item: core::marker::PhantomData<T>,
+}
+
+impl<'r, T> ArrayView<'r, T> {
+    fn new(value: Value<'r>) -> Self {
+        ArrayView {
+            elements: value.elements(),
+            _item: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<'r, T: Decode<'r>> Iterator for ArrayView<'r, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.elements.next().map(|value| value.decode())
+    }
+}
+
+/// Options for `get_transaction`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TransactionConfig {
+    /// `Confirmed` or `Finalized`; the client's commitment when `None`.
+    pub commitment: Option<Commitment>,
+}
+
+/// Confirmed transaction from `get_transaction`.
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionWithMeta<'r, 'buf> {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// Decoded wire-format transaction.
+    pub transaction: &'buf [u8],
+    pub meta: Option<TransactionMeta<'r>>,
+}
+
+impl<'buf> TransactionWithMeta<'_, 'buf> {
+    pub fn view(&self) -> Result<TransactionView<'buf>> {
+        TransactionView::parse(self.transaction)
+    }
+}
+
+/// Execution status and balance changes of a transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionMeta<'r> {
+    pub err: Option<TransactionError>,
+    pub fee: u64,
+    /// Lamports of each account key before execution, in message order.
+    pub pre_balances: Value<'r>,
+    pub post_balances: Value<'r>,
+    pub pre_token_balances: Option<Value<'r>>,
+    pub post_token_balances: Option<Value<'r>>,
+    pub log_messages: Option<Value<'r>>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+impl<'r> TransactionMeta<'r> {
+    pub fn pre_balances(&self) -> ArrayView<'r, u64> {
+        ArrayView::new(self.pre_balances)
+    }
+
+    pub fn post_balances(&self) -> ArrayView<'r, u64> {
+        ArrayView::new(self.post_balances)
+    }
+
+    /// Lamport change of the account at `index`.
+    pub fn balance_change(&self, index: usize) -> Option<i128> {
+        let pre = self.pre_balances.at(index)?.as_u64()?;
+        let post = self.post_balances.at(index)?.as_u64()?;
+        Some(post as i128 - pre as i128)
+    }
+
+    pub fn pre_token_balances(&self) -> Option<ArrayView<'r, TokenBalance>> {
+        self.pre_token_balances.map(ArrayView::new)
+    }
+
+    pub fn post_token_balances(&self) -> Option<ArrayView<'r, TokenBalance>> {
+        self.post_token_balances.map(ArrayView::new)
+    }
+
+    pub fn log_messages(&self) -> Option<ArrayView<'r, JsonStr<'r>>> {
+        self.log_messages.map(ArrayView::new)
+    }
+}
+
+impl<'r> Decode<'r> for TransactionMeta<'r> {
+    fn decode(value: Value<'r>) -> Result<Self> {
+        let non_null = |key| value.get(key).filter(|v: &Value| !v.is_null());
+        Ok(TransactionMeta {
+            err: value.field("err")?,
+            fee: value.field("fee")?,
+            pre_balances: value.field("preBalances")?,
+            post_balances: value.field("postBalances")?,
+            pre_token_balances: non_null("preTokenBalances"),
+            post_token_balances: non_null("postTokenBalances"),
+            log_messages: non_null("logMessages"),
+            compute_units_consumed: value.field("computeUnitsConsumed")?,
+        })
+    }
+}
+
+/// Token account balance recorded in transaction meta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenBalance {
+    pub account_index: u8,
+    pub mint: Address,
+    pub owner: Option<Address>,
+    pub program_id: Option<Address>,
+    pub amount: TokenAmount,
+}
+
+impl Decode<'_> for TokenBalance {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        Ok(TokenBalance {
+            account_index: value.field("accountIndex")?,
+            mint: value.field("mint")?,
+            owner: value.field("owner")?,
+            program_id: value.field("programId")?,
+            amount: value.field("uiTokenAmount")?,
+        })
+    }
+}
+
+impl Decode<'_> for TokenAmount {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        Ok(TokenAmount {
+            amount: value
+                .field::<&str>("amount")?
+                .parse()
+                .map_err(|_| SdkError::ResponseParseError)?,
+            decimals: value.field("decimals")?,
+        })
+    }
+}
+
+/// Which token accounts `get_token_accounts_by_owner` returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenAccountsFilter {
+    /// Accounts holding this mint.
+    Mint(Address),
+    /// All accounts owned by this token program.
+    ProgramId(Address),
+}
+
+/// Token account and its address, from `get_token_accounts_by_owner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyedTokenAccount {
+    pub pubkey: Address,
+    pub lamports: u64,
+    pub account: TokenAccount,
+}
+
+impl Decode<'_> for KeyedTokenAccount {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let account = value.get("account").ok_or(SdkError::ResponseParseError)?;
+        let data = account.get("data").ok_or(SdkError::ResponseParseError)?;
+        if data.kind() == Kind::Object {
+            let info = data
+                .path(&["parsed", "info"])
+                .ok_or(SdkError::ResponseParseError)?;
+            return Ok(KeyedTokenAccount {
+                pubkey: value.field("pubkey")?,
+                lamports: account.field("lamports")?,
+                account: parsed_token_account(info)?,
+            });
+        }
+        let data: JsonStr = data.at(0).ok_or(SdkError::ResponseParseError)?.decode()?;
+        // only the base layout is read, so extensions need no buffer space
+        let base64_len = TOKEN_ACCOUNT_LEN.div_ceil(3) * 4;
+        let mut bytes = [0u8; TOKEN_ACCOUNT_LEN];
+        STANDARD
+            .decode_slice(
+                data.raw()
+                    .get(..base64_len)
+                    .ok_or(SdkError::ResponseParseError)?,
+                &mut bytes,
+            )
+            .map_err(|_| SdkError::ResponseParseError)?;
+        Ok(KeyedTokenAccount {
+            pubkey: value.field("pubkey")?,
+            lamports: account.field("lamports")?,
+            account: TokenAccount::unpack(&bytes).map_err(|_| SdkError::ResponseParseError)?,
+        })
+    }
+}
+
+/// Most signatures `get_signature_statuses` sends in one request.
+pub const MAX_SIGNATURE_STATUSES: usize = 32;
+
+/// Processing state of a transaction, from `get_signature_statuses`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignatureStatus {
+    pub slot: u64,
+    /// Blocks since the transaction landed; `None` once rooted.
+    pub confirmations: Option<u64>,
+    pub err: Option<TransactionError>,
+    pub confirmation_status: Option<Commitment>,
+}
+
+impl SignatureStatus {
+    /// Whether the transaction has reached `commitment`.
+    pub fn satisfies(&self, commitment: Commitment) -> bool {
+        match self.confirmation_status {
+            Some(status) => status >= commitment,
+            // nodes without `confirmationStatus` report rooted as null confirmations
+            None => self.confirmations.is_none() || commitment == Commitment::Processed,
+        }
+    }
+}
+
+impl Decode<'_> for SignatureStatus {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        Ok(SignatureStatus {
+            slot: value.field("slot")?,
+            confirmations: value.field("confirmations")?,
+            err: value.field("err")?,
+            confirmation_status: value.field("confirmationStatus")?,
+        })
+    }
+}
+
+/// Statuses returned by `get_signature_statuses`, in request order; `None`
+/// for signatures the node has not seen.
+pub type SignatureStatuses<'r> = ArrayView<'r, Option<SignatureStatus>>;
+
+/// Async timer awaited between confirmation polls.
+pub trait Sleep {
+    fn sleep_ms(&self, ms: u32) -> impl Future<Output = ()>;
+}
+
+/// How `confirm_transaction` polls.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfirmConfig {
+    /// Target commitment; the client's commitment when `None`.
+    pub commitment: Option<Commitment>,
+    pub poll_interval_ms: u32,
+    /// Time from the first poll, on the caller's clock and including RPC
+    /// round trips, before giving up with `SdkError::Timeout`.
+    pub timeout_ms: u32,
+}
+
+impl Default for ConfirmConfig {
+    fn default() -> Self {
+        ConfirmConfig {
+            commitment: None,
+            poll_interval_ms: 2_000,
+            timeout_ms: 90_000,
+        }
+    }
+}
+
+/// Final state of a sent transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionOutcome {
+    /// Reached the requested commitment in `slot`.
+    Landed { slot: u64 },
+    /// Executed with an error, or was rejected by preflight (`slot` is `None`).
+    Failed {
+        slot: Option<u64>,
+        error: TransactionError,
+    },
+    /// The blockhash expired before the transaction landed; it never will.
+    Expired,
+}
+
+/// Blockhash returned by `get_latest_blockhash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatestBlockhash {
+    pub blockhash: Hash,
+    /// Last block height at which a transaction using `blockhash` can land.
+    pub last_valid_block_height: u64,
+    /// Slot the node answered at.
+    pub slot: u64,
+}
+
+impl LatestBlockhash {
+    fn parse(result: Value<'_>) -> Result<Self> {
+        let result = WithContext::parse(result)?;
+        Ok(LatestBlockhash {
+            blockhash: result.value.field("blockhash")?,
+            last_valid_block_height: result.value.field("lastValidBlockHeight")?,
+            slot: result.slot,
+        })
+    }
+}
+
+/// Approximate time between blocks, used to age cached blockhashes.
+pub const MS_PER_BLOCK: u64 = 400;
+/// Blocks a blockhash stays valid for after the block that produced it.
+pub const MAX_PROCESSING_AGE: u64 = 150;
+
+/// Reuses a blockhash across transactions until it is `margin` blocks from
+/// expiry, estimating the current block height from elapsed time.
+#[derive(Debug, Clone, Copy)]
+pub struct BlockhashCache {
+    /// Blocks before `last_valid_block_height` at which to fetch a new hash.
+    pub margin: u64,
+    cached: Option<CachedBlockhash>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CachedBlockhash {
+    latest: LatestBlockhash,
+    fetched_at_ms: u64,
+}
+
+impl BlockhashCache {
+    pub const fn new(margin: u64) -> Self {
+        BlockhashCache {
+            margin,
+            cached: None,
+        }
+    }
+
+    /// Returns the cached blockhash, refreshing it when it is close to expiry.
+    /// `now_ms` is any monotonic millisecond clock.
+    pub async fn get<C: AsyncClient>(
+        &mut self,
+        rpc: &RpcClient<'_, C>,
+        now_ms: u64,
+    ) -> Result<LatestBlockhash> {
+        if let Some(cached) = self.cached {
+            // the hash was the newest block at fetch time, so the height
+            // then was its validity window below the last valid height
+            let fetched_height = cached
+                .latest
+                .last_valid_block_height
+                .saturating_sub(MAX_PROCESSING_AGE);
+            let elapsed_blocks = now_ms.saturating_sub(cached.fetched_at_ms) / MS_PER_BLOCK;
+            let estimated_height = fetched_height + elapsed_blocks;
+            if estimated_height + self.margin < cached.latest.last_valid_block_height {
+                return Ok(cached.latest);
+            }
+        }
+        let latest = rpc.get_latest_blockhash(ContextConfig::default()).await?;
+        self.cached = Some(CachedBlockhash {
+            latest,
+            fetched_at_ms: now_ms,
+        });
+        Ok(latest)
+    }
+
+    /// Drops the cached blockhash, e.g. after `RpcErrorKind::BlockhashNotFound`.
+    pub fn invalidate(&mut self) {
+        self.cached = None;
+    }
+}
+
+/// Thin helper struct parameterized by an RpcClient impl.
+pub struct RpcClient<'a, C> {
+    url: &'a str,
+    commitment: Commitment,
+    client: C,
+}
+
+impl<'a, C> RpcClient<'a, C> {
+    /// Creates a new `Connection` with a **sync-only** client.
+    pub fn new_sync(url: &'a str, commitment: Commitment, client: C) -> Self
+    where
+        C: SyncClient,
+    {
+        Self {
+            url,
+            commitment,
+            client,
+        }
+    }
+
+    /// Creates a new `Connection` with an **async-only** client.
+    pub fn new_async(url: &'a str, commitment: Commitment, client: C) -> Self
+    where
+        C: AsyncClient,
+    {
+        Self {
+            url,
+            commitment,
+            client,
+        }
+    }
+}
+
+impl<'a, C: AsyncClient> RpcClient<'a, C> {
+    /// Creates a client for `cluster`'s URL, failing with
+    /// `SdkError::WrongCluster` if the endpoint is on another cluster.
+    pub async fn new_verified(
+        cluster: Cluster<'a>,
+        commitment: Commitment,
+        client: C,
+    ) -> Result<Self> {
+        let rpc = Self::new_async(cluster.url(), commitment, client);
+        rpc.verify_cluster(&cluster).await?;
+        Ok(rpc)
+    }
+
+    /// Checks the endpoint's genesis hash against the one `cluster` expects;
+    /// clusters without a known hash always pass.
+    pub async fn verify_cluster(&self, cluster: &Cluster<'_>) -> Result<()> {
+        let Some(expected) = cluster.genesis_hash() else {
+            return Ok(());
+        };
+        let actual = self.get_genesis_hash().await?;
+        match actual == expected {
+            true => Ok(()),
+            false => Err(SdkError::WrongCluster(actual)),
+        }
+    }
+
+    /// Starts a batch of calls sent together in one request.
+    pub fn batch(&self) -> Batch<'_, 'a, C> {
+        let mut body = heapless::String::new();
+        // an empty body always has room
+        let _ = body.push('[');
+        Batch {
+            rpc: self,
+            body,
+            calls: 0,
+        }
+    }
+
+    /// Posts `request` and returns the `result` member of the response.
+    async fn call<'r, const N: usize>(
+        &self,
+        request: &mut Request<N>,
+        resp_buffer: &'r mut [u8],
+    ) -> Result<Value<'r>> {
+        let response = self
+            .client
+            .post_json(self.url, request.finish()?, resp_buffer)
+            .await?;
+        RpcResponse::parse(response)?.into_result()
+    }
+
+    /// Posts a request written into the front of a buffer, receiving the
+    /// response into the rest of it.
+    async fn call_in_buffer<'r>(&self, request: JsonRequest<BufferBody<'r>>) -> Result<Value<'r>> {
+        let (body, resp_buffer) = request.split()?;
+        let response = self.client.post_json(self.url, body, resp_buffer).await?;
+        RpcResponse::parse(response)?.into_result()
+    }
+
+    /// Most recent blockhash, with the last block height at which a
+    /// transaction using it can still land.
+    pub async fn get_latest_blockhash(&self, config: ContextConfig) -> Result<LatestBlockhash> {
+        let mut request: Request<256> = Request::new("getLatestBlockhash")?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 512];
+        LatestBlockhash::parse(self.call(&mut request, &mut resp_buffer).await?)
+    }
+
+    /// Whether `blockhash` can still be used in a transaction.
+    pub async fn is_blockhash_valid(
+        &self,
+        blockhash: &Hash,
+        config: ContextConfig,
+    ) -> Result<WithContext<bool>> {
+        let mut request: Request<256> = Request::new("isBlockhashValid")?;
+        request.string(blockhash)?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 256];
+        let result = WithContext::parse(self.call(&mut request, &mut resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: result.value.decode()?,
+        })
+    }
+
+    pub async fn send_transaction(
+        &self,
+        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+    ) -> Result<Signature> {
+        self.send_transaction_with_config(transaction, SendTransactionConfig::default())
+            .await
+    }
+
+    pub async fn send_transaction_with_config(
+        &self,
+        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+        config: SendTransactionConfig,
+    ) -> Result<Signature> {
+        self.send_serialized(&serialize_for_send(transaction, config)?, config)
+            .await
+    }
+
+    async fn send_serialized(
+        &self,
+        transaction_bytes: &[u8],
+        config: SendTransactionConfig,
+    ) -> Result<Signature> {
+        let mut request: Request<2048> = Request::new("sendTransaction")?;
+        request
+            .string(Base64Display::new(transaction_bytes, &STANDARD))?
+            .begin_config()?
+            .entry("encoding", r#""base64""#)?;
+        if config.skip_preflight {
+            request.entry("skipPreflight", true)?;
+        }
+        if let Some(commitment) = config.preflight_commitment {
+            request.entry("preflightCommitment", format_args!(r#""{commitment}""#))?;
+        }
+        if let Some(max_retries) = config.max_retries {
+            request.entry("maxRetries", max_retries)?;
+        }
+        if let Some(slot) = config.min_context_slot {
+            request.entry("minContextSlot", slot)?;
+        }
+        request.end_object()?;
+        let mut resp_buffer = [0u8; 4096];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Fees paid in recent slots by transactions writing to all of
+    /// `addresses`, or cluster-wide when empty.
+    ///
+    /// The request is written to the front of `buffer` and the response
+    /// received into the rest: about 50 bytes per address plus 60 per slot.
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        addresses: &[&Address],
+        buffer: &mut [u8],
+    ) -> Result<heapless::Vec<PrioritizationFee, MAX_PRIORITIZATION_FEES>> {
+        if addresses.len() > MAX_PRIORITIZATION_FEE_ACCOUNTS {
+            return Err(SdkError::Invalid);
+        }
+        let mut request = JsonRequest::in_buffer(buffer, "getRecentPrioritizationFees")?;
+        request.raw(StringArray(addresses))?;
+        let result = self.call_in_buffer(request).await?;
+        let mut fees = heapless::Vec::new();
+        for fee in result.elements() {
+            // keep the newest slots if a node ever returns more
+            if fees.is_full() {
+                fees.remove(0);
+            }
+            let _ = fees.push(fee.decode()?);
+        }
+        Ok(fees)
+    }
+
+    /// Fetches an account, `None` in the value if it does not exist.
+    pub async fn get_account_info<'buf>(
+        &self,
+        address: &Address,
+        config: AccountInfoConfig,
+        data_buffer: &'buf mut [u8],
+        resp_buffer: &mut [u8],
+    ) -> Result<WithContext<Option<Account<'buf>>>> {
+        let mut request: Request<256> = Request::new("getAccountInfo")?;
+        request.string(address)?;
+        config.write(self.commitment, &mut request)?;
+        account_info(self.call(&mut request, resp_buffer).await?, data_buffer)
+    }
+
+    /// Fetches up to `MAX_MULTIPLE_ACCOUNTS` accounts in one request.
+    ///
+    /// With `DataBuffers::PerAccount` there must be a buffer for every
+    /// address; use `config.data_slice` to bound each account's footprint.
+    pub async fn get_multiple_accounts<'r, 'b, 'buf>(
+        &self,
+        addresses: &[Address],
+        config: AccountInfoConfig,
+        buffers: DataBuffers<'b, 'buf>,
+        resp_buffer: &'r mut [u8],
+    ) -> Result<WithContext<MultipleAccounts<'r, 'b, 'buf>>> {
+        if addresses.len() > MAX_MULTIPLE_ACCOUNTS {
+            return Err(SdkError::Invalid);
+        }
+        if let DataBuffers::PerAccount(buffers) = &buffers {
+            if buffers.len() < addresses.len() {
+                return Err(SdkError::Invalid);
+            }
+        }
+        let mut request: Request<5120> = Request::new("getMultipleAccounts")?;
+        request.raw(StringArray(addresses))?;
+        config.write(self.commitment, &mut request)?;
+        let result = WithContext::parse(self.call(&mut request, resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: MultipleAccounts {
+                elements: result.value.elements(),
+                buffers,
+                index: 0,
+            },
+        })
+    }
+
+    /// Hash of the cluster's genesis block, which identifies the cluster.
+    pub async fn get_genesis_hash(&self) -> Result<Hash> {
+        let mut request: Request<128> = Request::new("getGenesisHash")?;
+        let mut resp_buffer = [0u8; 128];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Asks the cluster's faucet to send `lamports` to `address`; only
+    /// devnet, testnet and local validators have one. Returns the signature
+    /// of the airdrop transaction, which can be passed to
+    /// `confirm_transaction`.
+    pub async fn request_airdrop(&self, address: &Address, lamports: u64) -> Result<Signature> {
+        let mut request: Request<256> = Request::new("requestAirdrop")?;
+        request
+            .string(address)?
+            .raw(lamports)?
+            .begin_config()?
+            .entry("commitment", format_args!(r#""{}""#, self.commitment))?;
+        request.end_object()?;
+        let mut resp_buffer = [0u8; 256];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Lamports held by `address`.
+    pub async fn get_balance(
+        &self,
+        address: &Address,
+        config: ContextConfig,
+    ) -> Result<WithContext<u64>> {
+        let mut request: Request<256> = Request::new("getBalance")?;
+        request.string(address)?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 256];
+        let result = WithContext::parse(self.call(&mut request, &mut resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: result.value.decode()?,
+        })
+    }
+
+    /// Lamports an account with `data_len` bytes of data needs to be rent exempt.
+    pub async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        let mut request: Request<256> = Request::new("getMinimumBalanceForRentExemption")?;
+        request.raw(data_len)?;
+        ContextConfig::default().write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 256];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Fee the cluster will charge for a compiled `message`; `None` once its
+    /// blockhash has expired.
+    pub async fn get_fee_for_message(
+        &self,
+        message: &[u8],
+        config: ContextConfig,
+    ) -> Result<WithContext<Option<u64>>> {
+        let mut request: Request<2048> = Request::new("getFeeForMessage")?;
+        request.string(Base64Display::new(message, &STANDARD))?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 256];
+        let result = WithContext::parse(self.call(&mut request, &mut resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: result.value.decode()?,
+        })
+    }
+
+    /// Checks whether the fee payer of a compiled `message` can cover its fee
+    /// plus the lamports it sends through the System program, including the
+    /// rent of the accounts it creates. All lookups share one batch request.
+    ///
+    /// Fails with `SdkError::Invalid` if the message's blockhash has expired
+    /// or it creates accounts of more than `MAX_RENT_LOOKUPS` distinct sizes.
+    pub async fn check_funding(&self, message: &[u8]) -> Result<FundingReport> {
+        let compiled = CompiledMessage::parse(message)?;
+        let payer = compiled.fee_payer().ok_or(SdkError::Invalid)?;
+        let mut spaces: heapless::Vec<u64, MAX_RENT_LOOKUPS> = heapless::Vec::new();
+        for space in system_transfers(&compiled).filter_map(|transfer| transfer.space) {
+            if !spaces.contains(&space) {
+                spaces.push(space).map_err(|_| SdkError::Invalid)?;
+            }
+        }
+
+        let mut batch = self.batch();
+        let balance = batch.get_balance(payer, ContextConfig::default())?;
+        let fee = batch.get_fee_for_message(message, ContextConfig::default())?;
+        let mut rents: heapless::Vec<Pending<u64>, MAX_RENT_LOOKUPS> = heapless::Vec::new();
+        for &space in &spaces {
+            let space = usize::try_from(space).map_err(|_| SdkError::Invalid)?;
+            // same capacity as `spaces`
+            let _ = rents.push(batch.get_minimum_balance_for_rent_exemption(space)?);
+        }
+        let mut resp_buffer = [0u8; 2048];
+        let response = batch.send(&mut resp_buffer).await?;
+        let balance = response.get(balance)?.value;
+        let fee = response.get(fee)?.value.ok_or(SdkError::Invalid)?;
+
+        let (mut rent, mut transfers) = (0u64, 0u64);
+        for transfer in system_transfers(&compiled) {
+            let mut lamports = transfer.lamports;
+            if let Some(space) = transfer.space {
+                let index = spaces.iter().position(|&s| s == space);
+                let minimum = response.get(rents[index.ok_or(SdkError::Invalid)?])?;
+                rent = rent.saturating_add(minimum);
+                // the runtime rejects new accounts below the minimum
+                lamports = lamports.max(minimum);
+            }
+            if transfer.from_payer {
+                transfers = transfers.saturating_add(lamports);
+            }
+        }
+        Ok(FundingReport {
+            balance,
+            fee,
+            rent,
+            transfers,
+            shortfall: fee.saturating_add(transfers).saturating_sub(balance),
+        })
+    }
+
+    /// Current block height, used to tell when a blockhash has expired.
+    pub async fn get_block_height(&self, config: ContextConfig) -> Result<u64> {
+        let mut request: Request<256> = Request::new("getBlockHeight")?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 256];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Slot the node has reached at the requested commitment.
+    pub async fn get_slot(&self, config: ContextConfig) -> Result<u64> {
+        let mut request: Request<256> = Request::new("getSlot")?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 256];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    pub async fn get_epoch_info(&self, config: ContextConfig) -> Result<EpochInfo> {
+        let mut request: Request<256> = Request::new("getEpochInfo")?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 512];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Health of the node itself; an unhealthy node is reported as
+    /// `NodeHealth::Behind` rather than as an error.
+    pub async fn get_health(&self) -> Result<NodeHealth> {
+        let mut request: Request<128> = Request::new("getHealth")?;
+        let mut resp_buffer = [0u8; 256];
+        let response = self
+            .client
+            .post_json(self.url, request.finish()?, &mut resp_buffer)
+            .await?;
+        let response = RpcResponse::parse(response)?;
+        if let Some(error) = response.error {
+            let rpc_error = parse_rpc_error(error)?;
+            if rpc_error.kind != RpcErrorKind::NodeUnhealthy {
+                return Err(SdkError::RpcError(rpc_error));
+            }
+            let slots_behind = match error.path(&["data", "numSlotsBehind"]) {
+                Some(slots) => slots.decode()?,
+                None => None,
+            };
+            return Ok(NodeHealth::Behind(slots_behind));
+        }
+        match response.into_result()?.as_str() {
+            Some(status) if status.eq_str("ok") => Ok(NodeHealth::Healthy),
+            _ => Err(SdkError::ResponseParseError),
+        }
+    }
+
+    /// Software version and feature set the node runs.
+    pub async fn get_version(&self) -> Result<NodeVersion> {
+        let mut request: Request<128> = Request::new("getVersion")?;
+        let mut resp_buffer = [0u8; 256];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Estimated Unix time in seconds at which `slot` was produced; `None`
+    /// if the node has no timestamp for it.
+    pub async fn get_block_time(&self, slot: u64) -> Result<Option<i64>> {
+        let mut request: Request<128> = Request::new("getBlockTime")?;
+        request.raw(slot)?;
+        let mut resp_buffer = [0u8; 256];
+        self.call(&mut request, &mut resp_buffer).await?.decode()
+    }
+
+    /// Signatures of transactions that referenced `address`, newest first.
+    pub async fn get_signatures_for_address<'r>(
+        &self,
+        address: &Address,
+        config: SignaturesForAddressConfig,
+        resp_buffer: &'r mut [u8],
+    ) -> Result<ArrayView<'r, SignatureInfo<'r>>> {
+        let mut request: Request<512> = Request::new("getSignaturesForAddress")?;
+        request.string(address)?.begin_config()?.entry(
+            "commitment",
+            format_args!(r#""{}""#, config.commitment.unwrap_or(self.commitment)),
+        )?;
+        if let Some(before) = config.before {
+            request.entry("before", format_args!(r#""{before}""#))?;
+        }
+        if let Some(until) = config.until {
+            request.entry("until", format_args!(r#""{until}""#))?;
+        }
+        if let Some(limit) = config.limit {
+            request.entry("limit", limit)?;
+        }
+        if let Some(slot) = config.min_context_slot {
+            request.entry("minContextSlot", slot)?;
+        }
+        request.end_object()?;
+        Ok(ArrayView::new(self.call(&mut request, resp_buffer).await?))
+    }
+
+    /// Fetches a confirmed transaction and its meta; `None` if the node does
+    /// not know it. The transaction is decoded into `transaction_buffer`.
+    pub async fn get_transaction<'r, 'buf>(
+        &self,
+        signature: &Signature,
+        config: TransactionConfig,
+        transaction_buffer: &'buf mut [u8],
+        resp_buffer: &'r mut [u8],
+    ) -> Result<Option<TransactionWithMeta<'r, 'buf>>> {
+        let mut request: Request<256> = Request::new("getTransaction")?;
+        request
+            .string(signature)?
+            .begin_config()?
+            .entry(
+                "commitment",
+                format_args!(r#""{}""#, config.commitment.unwrap_or(self.commitment)),
+            )?
+            .entry("encoding", r#""base64""#)?
+            .entry("maxSupportedTransactionVersion", 0)?
+            .end_object()?;
+        let result = self.call(&mut request, resp_buffer).await?;
+        if result.is_null() {
+            return Ok(None);
+        }
+        let encoded: JsonStr = result
+            .path(&["transaction"])
+            .and_then(|transaction| transaction.at(0))
+            .ok_or(SdkError::ResponseParseError)?
+            .decode()?;
+        let len = STANDARD
+            .decode_slice(encoded.raw(), transaction_buffer)
+            .map_err(|_| SdkError::ResponseParseError)?;
+        Ok(Some(TransactionWithMeta {
+            slot: result.field("slot")?,
+            block_time: result.field("blockTime")?,
+            transaction: &transaction_buffer[..len],
+            meta: result.field("meta")?,
+        }))
+    }
+
+    /// Balance of the token account at `address`, with the mint's decimals.
+    pub async fn get_token_account_balance(
+        &self,
+        address: &Address,
+        config: ContextConfig,
+    ) -> Result<WithContext<TokenAmount>> {
+        let mut request: Request<256> = Request::new("getTokenAccountBalance")?;
+        request.string(address)?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 512];
+        let result = WithContext::parse(self.call(&mut request, &mut resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: result.value.decode()?,
+        })
+    }
+
+    /// Total supply of `mint`.
+    pub async fn get_token_supply(
+        &self,
+        mint: &Address,
+        config: ContextConfig,
+    ) -> Result<WithContext<TokenAmount>> {
+        let mut request: Request<256> = Request::new("getTokenSupply")?;
+        request.string(mint)?;
+        config.write(self.commitment, &mut request)?;
+        let mut resp_buffer = [0u8; 512];
+        let result = WithContext::parse(self.call(&mut request, &mut resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: result.value.decode()?,
+        })
+    }
+
+    /// Token accounts owned by `owner`, filtered by mint or token program.
+    ///
+    /// `JsonParsed` costs more response buffer but also reads Token-2022
+    /// accounts whose extensions push the data past the base layout.
+    pub async fn get_token_accounts_by_owner<'r>(
+        &self,
+        owner: &Address,
+        filter: TokenAccountsFilter,
+        encoding: AccountEncoding,
+        config: ContextConfig,
+        resp_buffer: &'r mut [u8],
+    ) -> Result<WithContext<ArrayView<'r, KeyedTokenAccount>>> {
+        let mut request: Request<384> = Request::new("getTokenAccountsByOwner")?;
+        request.string(owner)?.begin_config()?;
+        match filter {
+            TokenAccountsFilter::Mint(mint) => {
+                request.entry("mint", format_args!(r#""{mint}""#))?
+            }
+            TokenAccountsFilter::ProgramId(program_id) => {
+                request.entry("programId", format_args!(r#""{program_id}""#))?
+            }
+        };
+        request.end_object()?.begin_config()?.entry(
+            "commitment",
+            format_args!(r#""{}""#, config.commitment.unwrap_or(self.commitment)),
+        )?;
+        request.entry("encoding", format_args!(r#""{}""#, encoding.as_str()))?;
+        if let Some(slot) = config.min_context_slot {
+            request.entry("minContextSlot", slot)?;
+        }
+        request.end_object()?;
+        let result = WithContext::parse(self.call(&mut request, resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: ArrayView::new(result.value),
+        })
+    }
+
+    /// Looks up up to `MAX_SIGNATURE_STATUSES` signatures. Without
+    /// `search_transaction_history` only recent transactions are found.
+    pub async fn get_signature_statuses<'r>(
+        &self,
+        signatures: &[Signature],
+        search_transaction_history: bool,
+        resp_buffer: &'r mut [u8],
+    ) -> Result<WithContext<SignatureStatuses<'r>>> {
+        if signatures.len() > MAX_SIGNATURE_STATUSES {
+            return Err(SdkError::Invalid);
+        }
+        let mut request: Request<3072> = Request::new("getSignatureStatuses")?;
+        request
+            .raw(StringArray(signatures))?
+            .begin_config()?
+            .entry("searchTransactionHistory", search_transaction_history)?
+            .end_object()?;
+        let result = WithContext::parse(self.call(&mut request, resp_buffer).await?)?;
+        Ok(WithContext {
+            slot: result.slot,
+            value: ArrayView::new(result.value),
+        })
+    }
+
+    /// Polls until `signature` reaches the target commitment, fails, or
+    /// `last_valid_block_height` passes before it does. `now_ms` is any
+    /// monotonic millisecond clock and bounds the whole wait.
+    ///
+    /// The block height is read before each status, so a transaction seen
+    /// at the target commitment is never reported as expired; one still
+    /// below it once the blockhash has expired is.
+    pub async fn confirm_transaction(
+        &self,
+        signature: &Signature,
+        last_valid_block_height: u64,
+        config: ConfirmConfig,
+        sleep: &impl Sleep,
+        now_ms: impl Fn() -> u64,
+    ) -> Result<TransactionOutcome> {
+        let commitment = config.commitment.unwrap_or(self.commitment);
+        let deadline = now_ms().saturating_add(config.timeout_ms as u64);
+        loop {
+            let block_height = self.get_block_height(ContextConfig::default()).await?;
+            let mut resp_buffer = [0u8; 512];
+            let status = self
+                .get_signature_statuses(core::slice::from_ref(signature), false, &mut resp_buffer)
+                .await?
+                .value
+                .next()
+                .ok_or(SdkError::ResponseParseError)??;
+            match status {
+                Some(SignatureStatus {
+                    slot,
+                    err: Some(error),
+                    ..
+                }) => {
+                    return Ok(TransactionOutcome::Failed {
+                        slot: Some(slot),
+                        error,
+                    })
+                }
+                Some(status) if status.satisfies(commitment) => {
+                    return Ok(TransactionOutcome::Landed { slot: status.slot })
+                }
+                _ if block_height > last_valid_block_height => {
+                    return Ok(TransactionOutcome::Expired)
+                }
+                _ => {}
+            }
+            let remaining = deadline.saturating_sub(now_ms());
+            if remaining == 0 {
+                return Err(SdkError::Timeout);
+            }
+            sleep
+                .sleep_ms(remaining.min(config.poll_interval_ms as u64) as u32)
+                .await;
+        }
+    }
+
+    /// Sends `transaction` and waits for its outcome with `confirm_transaction`.
+    /// Preflight rejections are reported as `Failed` or `Expired` rather than
+    /// as errors.
+    pub async fn send_and_confirm_transaction(
+        &self,
+        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+        last_valid_block_height: u64,
+        send_config: SendTransactionConfig,
+        config: ConfirmConfig,
+        sleep: &impl Sleep,
+        now_ms: impl Fn() -> u64,
+    ) -> Result<(Signature, TransactionOutcome)> {
+        let transaction_bytes = serialize_for_send(transaction, send_config)?;
+        let signature = match self.send_serialized(&transaction_bytes, send_config).await {
+            Ok(signature) => signature,
+            Err(SdkError::RpcError(RpcError {
+                transaction_error: Some(error),
+                kind,
+                ..
+            })) => {
+                let signature = transaction_signature(&transaction_bytes)?;
+                let outcome = match kind {
+                    RpcErrorKind::BlockhashNotFound => TransactionOutcome::Expired,
+                    _ => TransactionOutcome::Failed { slot: None, error },
+                };
+                return Ok((signature, outcome));
+            }
+            Err(e) => return Err(e),
+        };
+        let outcome = self
+            .confirm_transaction(&signature, last_valid_block_height, config, sleep, now_ms)
+            .await?;
+        Ok((signature, outcome))
+    }
+}
+
+/// Most calls a `Batch` can carry.
+pub const MAX_BATCH_CALLS: usize = 16;
+/// Size of a batch request body.
+pub const MAX_BATCH_BODY_LEN: usize = 4096;
+
+/// Most distinct account sizes `check_funding` looks up the rent of; the
+/// balance and fee take the other two calls of its batch.
+pub const MAX_RENT_LOOKUPS: usize = MAX_BATCH_CALLS - 2;
+
+/// Call queued in a `Batch`, redeemed for its result with `BatchResponse::get`.
+#[derive(Debug, Clone, Copy)]
+pub struct Pending<T> {
+    id: u32,
+    decode: fn(Value<'_>) -> Result<T>,
+}
+
+/// `getAccountInfo` call queued in a `Batch`, redeemed with
+/// `BatchResponse::account_info`.
+#[derive(Debug, Clone, Copy)]
+pub struct PendingAccount {
+    id: u32,
+}
+
+/// Several calls sent as one JSON-RPC batch, so they share a single
+/// connection. Created by `RpcClient::batch`.
+pub struct Batch<'c, 'a, C> {
+    rpc: &'c RpcClient<'a, C>,
+    body: heapless::String<MAX_BATCH_BODY_LEN>,
+    calls: u32,
+}
+
+impl<C: AsyncClient> Batch<'_, '_, C> {
+    /// Queues `get_latest_blockhash`.
+    pub fn get_latest_blockhash(
+        &mut self,
+        config: ContextConfig,
+    ) -> Result<Pending<LatestBlockhash>> {
+        let mut request: Request<256> = self.request("getLatestBlockhash")?;
+        config.write(self.rpc.commitment, &mut request)?;
+        let id = self.push(&mut request)?;
+        Ok(Pending {
+            id,
+            decode: LatestBlockhash::parse,
+        })
+    }
+
+    /// Queues `get_balance`.
+    pub fn get_balance(
+        &mut self,
+        address: &Address,
+        config: ContextConfig,
+    ) -> Result<Pending<WithContext<u64>>> {
+        let mut request: Request<256> = self.request("getBalance")?;
+        request.string(address)?;
+        config.write(self.rpc.commitment, &mut request)?;
+        let id = self.push(&mut request)?;
+        Ok(Pending {
+            id,
+            decode: |result| result.decode(),
+        })
+    }
+
+    /// Queues `get_fee_for_message`.
+    pub fn get_fee_for_message(
+        &mut self,
+        message: &[u8],
+        config: ContextConfig,
+    ) -> Result<Pending<WithContext<Option<u64>>>> {
+        let mut request: Request<2048> = self.request("getFeeForMessage")?;
+        request.string(Base64Display::new(message, &STANDARD))?;
+        config.write(self.rpc.commitment, &mut request)?;
+        let id = self.push(&mut request)?;
+        Ok(Pending {
+            id,
+            decode: |result| result.decode(),
+        })
+    }
+
+    /// Queues `get_minimum_balance_for_rent_exemption`.
+    pub fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<Pending<u64>> {
+        let mut request: Request<256> = self.request("getMinimumBalanceForRentExemption")?;
+        request.raw(data_len)?;
+        ContextConfig::default().write(self.rpc.commitment, &mut request)?;
+        let id = self.push(&mut request)?;
+        Ok(Pending {
+            id,
+            decode: |result| result.decode(),
+        })
+    }
+
+    /// Queues `get_account_info`.
+    pub fn get_account_info(
+        &mut self,
+        address: &Address,
+        config: AccountInfoConfig,
+    ) -> Result<PendingAccount> {
+        let mut request: Request<256> = self.request("getAccountInfo")?;
+        request.string(address)?;
+        config.write(self.rpc.commitment, &mut request)?;
+        let id = self.push(&mut request)?;
+        Ok(PendingAccount { id })
+    }
+
+    /// Queues a status lookup of a single recent signature.
+    pub fn get_signature_status(
+        &mut self,
+        signature: &Signature,
+    ) -> Result<Pending<WithContext<Option<SignatureStatus>>>> {
+        let mut request: Request<256> = self.request("getSignatureStatuses")?;
+        request.raw(StringArray(core::slice::from_ref(signature)))?;
+        let id = self.push(&mut request)?;
+        Ok(Pending {
+            id,
+            decode: |result| {
+                let result = WithContext::parse(result)?;
+                Ok(WithContext {
+                    slot: result.slot,
+                    value: result
+                        .value
+                        .at(0)
+                        .ok_or(SdkError::ResponseParseError)?
+                        .decode()?,
+                })
+            },
+        })
+    }
+
+    /// Sends the queued calls. Failing calls only fail their own `get`.
+    pub async fn send<'r>(mut self, resp_buffer: &'r mut [u8]) -> Result<BatchResponse<'r>> {
+        if self.calls == 0 {
+            return Err(SdkError::Invalid);
+        }
+        self.body.push(']').map_err(|_| SdkError::Serialize)?;
+        let response = self
+            .rpc
+            .client
+            .post_json(self.rpc.url, self.body.as_bytes(), resp_buffer)
+            .await?;
+        let items = Value::parse(response)?;
+        if items.kind() != Kind::Array {
+            // the whole batch was rejected, e.g. as too large
+            RpcResponse::from_value(items)?.into_result()?;
+            return Err(SdkError::ResponseParseError);
+        }
+        Ok(BatchResponse { items })
+    }
+
+    fn request<const N: usize>(&self, method: &str) -> Result<Request<N>> {
+        if self.calls as usize >= MAX_BATCH_CALLS {
+            return Err(SdkError::Invalid);
+        }
+        Request::with_id(method, self.calls + 1)
+    }
+
+    /// Appends a finished request to the body and returns its id.
+    fn push<const N: usize>(&mut self, request: &mut Request<N>) -> Result<u32> {
+        let request = core::str::from_utf8(request.finish()?).map_err(|_| SdkError::Serialize)?;
+        let separator = if self.calls == 0 { "" } else { "," };
+        self.body
+            .write_fmt(format_args!("{separator}{request}"))
+            .map_err(|_| SdkError::Serialize)?;
+        self.calls += 1;
+        Ok(self.calls)
+    }
+}
+
+/// Responses to a `Batch`, matched to their calls by id.
+#[derive(Debug, Clone, Copy)]
+pub struct BatchResponse<'r> {
+    items: Value<'r>,
+}
+
+impl<'r> BatchResponse<'r> {
+    /// Result of a queued call, or the error the node returned for it.
+    pub fn get<T>(&self, pending: Pending<T>) -> Result<T> {
+        (pending.decode)(self.result(pending.id)?)
+    }
+
+    /// Result of a queued `get_account_info`, writing the account data into
+    /// `data_buffer`.
+    pub fn account_info<'buf>(
+        &self,
+        pending: PendingAccount,
+        data_buffer: &'buf mut [u8],
+    ) -> Result<WithContext<Option<Account<'buf>>>> {
+        account_info(self.result(pending.id)?, data_buffer)
+    }
+
+    fn result(&self, id: u32) -> Result<Value<'r>> {
+        // decode only the matching entry, so a malformed reply to another
+        // call cannot fail this one
+        for item in self.items.elements() {
+            if item.get("id").and_then(|id| id.as_u64()) != Some(id as u64) {
+                continue;
+            }
+            if let Ok(response) = RpcResponse::from_value(item) {
+                return response.into_result();
+            }
+        }
+        Err(SdkError::ResponseParseError)
+    }
+}
+
+/// Size of the buffer holding the parts of a streamed response outside its
+/// result array: the envelope, context or an error.
+const STREAM_SKELETON_LEN: usize = 512;
+
+impl<'a, C: AsyncStreamingClient> RpcClient<'a, C> {
+    /// Accounts owned by `program_id`, streamed from the response body so
+    /// the result never has to fit in RAM.
+    ///
+    /// Each element is collected in `element_buffer` and its data decoded
+    /// into `data_buffer` before being handed to `on_account`; both are
+    /// reused for the next account. Use `config.data_slice` to bound their
+    /// size. Returns the context slot when `config.with_context` is set.
+    pub async fn get_program_accounts(
+        &self,
+        program_id: &Address,
+        config: ProgramAccountsConfig<'_>,
+        element_buffer: &mut [u8],
+        data_buffer: &mut [u8],
+        mut on_account: impl FnMut(KeyedAccount<'_>) -> Result<()>,
+    ) -> Result<Option<u64>> {
+        if config.filters.len() > MAX_PROGRAM_ACCOUNTS_FILTERS
+            || config.filters.iter().any(|filter| {
+                matches!(filter, ProgramAccountsFilter::Memcmp { bytes, .. } if bytes.len() > MAX_MEMCMP_BYTES)
+            })
+        {
+            return Err(SdkError::Invalid);
+        }
+        let mut request: Request<1024> = Request::new("getProgramAccounts")?;
+        request.string(program_id)?.begin_config()?.entry(
+            "commitment",
+            format_args!(r#""{}""#, config.commitment.unwrap_or(self.commitment)),
+        )?;
+        request.entry("encoding", r#""base64""#)?;
+        if let Some(slice) = config.data_slice {
+            request.entry(
+                "dataSlice",
+                format_args!(r#"{{"offset":{},"length":{}}}"#, slice.offset, slice.length),
+            )?;
+        }
+        if !config.filters.is_empty() {
+            request.entry("filters", Filters(config.filters))?;
+        }
+        if config.with_context {
+            request.entry("withContext", true)?;
+        }
+        if let Some(slot) = config.min_context_slot {
+            request.entry("minContextSlot", slot)?;
+        }
+        request.end_object()?;
+
+        let mut skeleton = [0u8; STREAM_SKELETON_LEN];
+        let mut stream = ArrayStream::new(&mut skeleton, element_buffer);
+        self.client
+            .post_json_streaming(self.url, request.finish()?, |chunk| {
+                stream.feed(chunk, |element| {
+                    on_account(KeyedAccount::parse(
+                        Value::parse(element)?,
+                        &mut *data_buffer,
+                    )?)
+                })
+            })
+            .await?;
+        let result = RpcResponse::parse(stream.skeleton())?.into_result()?;
+        if !stream.is_complete() {
+            return Err(SdkError::ResponseParseError);
+        }
+        match config.with_context {
+            true => Ok(Some(WithContext::parse(result)?.slot)),
+            false => Ok(None),
+        }
+    }
+
+    /// Dry-runs `transaction`, streaming the response so log lines never
+    /// have to be buffered together.
+    ///
+    /// Each log line is collected in `log_buffer` and handed to `logs`; an
+    /// empty `log_buffer` skips them. The request is written to the front of
+    /// `buffer`, and everything else in the result is kept in the rest.
+    pub async fn simulate_transaction<'r>(
+        &self,
+        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+        config: SimulateTransactionConfig<'_>,
+        buffer: &'r mut [u8],
+        log_buffer: &mut [u8],
+        mut logs: impl FnMut(JsonStr<'_>),
+    ) -> Result<WithContext<SimulationResult<'r>>> {
+        let transaction_bytes = transaction.serialize()?;
+        let mut request = JsonRequest::in_buffer(buffer, "simulateTransaction")?;
+        request
+            .string(Base64Display::new(&transaction_bytes, &STANDARD))?
+            .begin_config()?
+            .entry("encoding", r#""base64""#)?
+            .entry(
+                "commitment",
+                format_args!(r#""{}""#, config.commitment.unwrap_or(self.commitment)),
+            )?
+            .entry("sigVerify", config.sig_verify)?
+            .entry("replaceRecentBlockhash", config.replace_recent_blockhash)?;
+        if let Some(addresses) = config.accounts {
+            request
+                .begin_object("accounts")?
+                .entry("encoding", r#""base64""#)?
+                .entry("addresses", StringArray(addresses))?
+                .end_object()?;
+        }
+        if config.inner_instructions {
+            request.entry("innerInstructions", true)?;
+        }
+        if let Some(slot) = config.min_context_slot {
+            request.entry("minContextSlot", slot)?;
+        }
+        request.end_object()?;
+
+        let (body, resp_buffer) = request.split()?;
+        let mut stream =
+            ArrayStream::with_path(resp_buffer, log_buffer, &["result", "value", "logs"]);
+        self.client
+            .post_json_streaming(self.url, body, |chunk| {
+                stream.feed(chunk, |line| {
+                    logs(Value::parse(line)?.decode()?);
+                    Ok(())
+                })
+            })
+            .await?;
+        let result = RpcResponse::parse(stream.into_skeleton())?.into_result()?;
+        let result = WithContext::parse(result)?;
+        let value = result.value;
+        let non_null = |key| value.get(key).filter(|v: &Value| !v.is_null());
+        Ok(WithContext {
+            slot: result.slot,
+            value: SimulationResult {
+                err: value.field("err")?,
+                units_consumed: value.field("unitsConsumed")?,
+                return_data: value.field("returnData")?,
+                accounts: non_null("accounts"),
+                inner_instructions: non_null("innerInstructions"),
+            },
+        })
+    }
+
+    /// Compute units `transaction` consumes, simulated with the maximum limit
+    /// so the default per-instruction limit cannot cut it short.
+    ///
+    /// `buffer` holds the request and response as in `simulate_transaction`;
+    /// 4 KiB fits any transaction.
+    pub async fn estimate_compute_units(
+        &self,
+        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+        buffer: &mut [u8],
+    ) -> Result<u32> {
+        let limit = set_compute_unit_limit_data(MAX_COMPUTE_UNIT_LIMIT);
+        let instructions = with_compute_budget(
+            &[compute_budget_instruction(&limit)],
+            transaction.instructions,
+        )?;
+        let simulated = Transaction {
+            signers: transaction.signers,
+            instructions: &instructions,
+            recent_blockhash: transaction.recent_blockhash,
+        };
+        let config = SimulateTransactionConfig {
+            replace_recent_blockhash: true,
+            ..Default::default()
+        };
+        let result = self
+            .simulate_transaction(&simulated, config, buffer, &mut [], |_| {})
+            .await?
+            .value;
+        if let Some(error) = result.err {
+            return Err(SdkError::TransactionFailed(error));
+        }
+        let units = result.units_consumed.ok_or(SdkError::ResponseParseError)?;
+        u32::try_from(units).map_err(|_| SdkError::ResponseParseError)
+    }
+
+    /// Simulates `transaction`, sets its compute unit limit to the consumed
+    /// units plus `config.margin_percent`, optionally prices it from recent
+    /// fees, and sends it signed by the same signers.
+    ///
+    /// `buffer` is reused for the simulation and the fee lookup; see
+    /// `estimate_compute_units` and `get_recent_prioritization_fees` for
+    /// their sizes.
+    pub async fn send_with_compute_unit_limit(
+        &self,
+        transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+        config: ComputeUnitConfig,
+        send_config: SendTransactionConfig,
+        buffer: &mut [u8],
+    ) -> Result<(Signature, AppliedComputeBudget)> {
+        let consumed = self.estimate_compute_units(transaction, buffer).await?;
+        let unit_limit = compute_unit_limit_with_margin(consumed, config.margin_percent);
+        let unit_price = match config.priority_fee {
+            Some(estimator) => {
+                let fees = self
+                    .get_recent_prioritization_fees(&transaction.writable_accounts(), buffer)
+                    .await?;
+                Some(estimator.estimate(&fees))
+            }
+            None => send_config.compute_unit_price,
+        };
+        let limit = set_compute_unit_limit_data(unit_limit);
+        let price = set_compute_unit_price_data(unit_price.unwrap_or_default());
+        let budget = [
+            compute_budget_instruction(&limit),
+            compute_budget_instruction(&price),
+        ];
+        let budget = match unit_price {
+            Some(_) => &budget[..],
+            None => &budget[..1],
+        };
+        let instructions = with_compute_budget(budget, transaction.instructions)?;
+        let sized = Transaction {
+            signers: transaction.signers,
+            instructions: &instructions,
+            recent_blockhash: transaction.recent_blockhash,
+        };
+        // the price is already part of the budget
+        let send_config = SendTransactionConfig {
+            compute_unit_price: None,
+            ..send_config
+        };
+        let signature = self
+            .send_transaction_with_config(&sized, send_config)
+            .await?;
+        Ok((
+            signature,
+            AppliedComputeBudget {
+                unit_limit,
+                unit_price,
+            },
+        ))
+    }
+}
+
+/// Writes filters as a JSON array of filter objects.
+struct Filters<'a>(&'a [ProgramAccountsFilter<'a>]);
+
+impl fmt::Display for Filters<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, filter) in self.0.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{separator}{filter}")?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// Wire format of `transaction`, priced as `config.compute_unit_price` asks.
+fn serialize_for_send(
+    transaction: &Transaction<'_, '_, '_, '_, '_, '_, '_, '_>,
+    config: SendTransactionConfig,
+) -> Result<heapless::Vec<u8, PACKET_DATA_SIZE>> {
+    let Some(unit_price) = config.compute_unit_price else {
+        return transaction.serialize();
+    };
+    let price = set_compute_unit_price_data(unit_price);
+    let instructions = with_compute_budget(
+        &[compute_budget_instruction(&price)],
+        transaction.instructions,
+    )?;
+    Transaction {
+        signers: transaction.signers,
+        instructions: &instructions,
+        recent_blockhash: transaction.recent_blockhash,
+    }
+    .serialize()
+}
+
+/// The fee payer's signature, which identifies the transaction: the first
+/// one in its wire format, after the one-byte signature count.
+fn transaction_signature(transaction_bytes: &[u8]) -> Result<Signature> {
+    let bytes: [u8; 64] = transaction_bytes
+        .get(1..65)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(SdkError::Invalid)?;
+    Ok(Signature::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compute_budget::COMPUTE_BUDGET_PROGRAM_ID,
+        crypto::Keypair,
+        instruction::{AccountMeta, Instruction},
+        transaction_error::InstructionError,
+    };
+    use alloc::{format, string::String, vec, vec::Vec};
+    use core::cell::RefCell;
+
+    /// Answers each request with the next response queued for its method,
+    /// repeating the last one, and records the bodies sent.
+    struct MockClient {
+        responses: Vec<(&'static str, Vec<&'static [u8]>)>,
+        requests: RefCell<Vec<String>>,
+    }
+
+    impl MockClient {
+        /// Answers every request with `response`.
+        fn new(response: &'static [u8]) -> Self {
+            Self::with(&[("", &[response])])
+        }
+
+        /// Answers requests for each method, or any method for `""`.
+        fn with(responses: &[(&'static str, &[&'static [u8]])]) -> Self {
+            MockClient {
+                responses: responses
+                    .iter()
+                    .map(|(method, queue)| (*method, queue.to_vec()))
+                    .collect(),
+                requests: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn respond(&self, json_body: &[u8]) -> &'static [u8] {
+            let body = String::from_utf8(json_body.to_vec()).unwrap();
+            let method = |body: &str| {
+                let start = body.find(r#""method":""#).unwrap() + 10;
+                String::from(&body[start..start + body[start..].find('"').unwrap()])
+            };
+            let name = method(&body);
+            let mut requests = self.requests.borrow_mut();
+            let earlier = requests.iter().filter(|r| method(r) == name).count();
+            requests.push(body);
+            let (_, queue) = self
+                .responses
+                .iter()
+                .find(|(m, _)| m.is_empty() || *m == name)
+                .unwrap_or_else(|| panic!("unexpected {name}"));
+            queue[earlier.min(queue.len() - 1)]
+        }
+
+        fn sent(&self, method: &str) -> usize {
+            let needle = format!(r#""method":"{method}""#);
+            self.requests
+                .borrow()
+                .iter()
+                .filter(|r| r.contains(&needle))
+                .count()
+        }
+    }
+
+    impl AsyncClient for MockClient {
+        async fn post_json<'a>(
+            &self,
+            _url: &str,
+            json_body: &[u8],
+            resp_buffer: &'a mut [u8],
+        ) -> Result<&'a [u8]> {
+            let body = self.respond(json_body);
+            let response = resp_buffer
+                .get_mut(..body.len())
+                .ok_or(SdkError::ResponseParseError)?;
+            response.copy_from_slice(body);
+            Ok(response)
+        }
+    }
+
+    impl AsyncStreamingClient for MockClient {
+        async fn post_json_streaming(
+            &self,
+            _url: &str,
+            json_body: &[u8],
+            mut on_chunk: impl FnMut(&[u8]) -> Result<()>,
+        ) -> Result<()> {
+            // small chunks so values straddle their boundaries
+            for chunk in self.respond(json_body).chunks(5) {
+                on_chunk(chunk)?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Polls `future` to completion; the mock never returns `Pending`.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| {},
+            |_| {},
+            |_| {},
+        );
+        // SAFETY: the vtable functions ignore the data pointer
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let mut context = Context::from_waker(&waker);
+        let mut future = core::pin::pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    const LATEST_BLOCKHASH: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":320},"value":{"blockhash":"4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY","lastValidBlockHeight":450}}}"#;
+
+    fn account_json(data: &[u8], space: usize) -> alloc::string::String {
+        let mut encoded = [0u8; 256];
+        let len = STANDARD.encode_slice(data, &mut encoded).unwrap();
+        format!(
+            r#"{{"lamports":1,"owner":"{}","data":["{}","base64"],"executable":false,"rentEpoch":0,"space":{}}}"#,
+            TOKEN_PROGRAM_ID,
+            core::str::from_utf8(&encoded[..len]).unwrap(),
+            space
+        )
+    }
+
+    #[test]
+    fn unpacks_only_whole_account_data() {
+        let mut token_account = [0u8; TOKEN_ACCOUNT_LEN];
+        token_account[64..72].copy_from_slice(&5u64.to_le_bytes());
+        token_account[108] = 1;
+        let mut buffer = [0u8; TOKEN_ACCOUNT_LEN];
+
+        let json = account_json(&token_account, TOKEN_ACCOUNT_LEN);
+        let account = Account::parse(Value::parse(json.as_bytes()).unwrap(), &mut buffer).unwrap();
+        assert!(matches!(
+            account.parsed,
+            Some(ParsedAccount::TokenAccount(TokenAccount { amount: 5, .. }))
+        ));
+
+        // the first MINT_LEN bytes of a token account would decode as a mint
+        let json = account_json(&token_account[..MINT_LEN], TOKEN_ACCOUNT_LEN);
+        let account = Account::parse(Value::parse(json.as_bytes()).unwrap(), &mut buffer).unwrap();
+        assert_eq!(account.data.len(), MINT_LEN);
+        assert_eq!(account.space, TOKEN_ACCOUNT_LEN as u64);
+        assert_eq!(account.parsed, None);
+    }
+
+    #[test]
+    fn unpacks_token_layouts_by_program() {
+        let mut mint = [0u8; MINT_LEN];
+        mint[44] = 6;
+        mint[45] = 1;
+        let mut account = [0u8; TOKEN_ACCOUNT_LEN + 2];
+        account[108] = 1;
+        let is_mint = |data: &[u8], owner| match ParsedAccount::unpack(owner, data) {
+            Some(ParsedAccount::Mint(_)) => Some(true),
+            Some(ParsedAccount::TokenAccount(_)) => Some(false),
+            _ => None,
+        };
+
+        assert_eq!(is_mint(&mint, &TOKEN_PROGRAM_ID), Some(true));
+        assert_eq!(
+            is_mint(&account[..TOKEN_ACCOUNT_LEN], &TOKEN_PROGRAM_ID),
+            Some(false)
+        );
+        // legacy accounts have exact sizes and no type byte
+        assert_eq!(is_mint(&account, &TOKEN_PROGRAM_ID), None);
+        assert_eq!(is_mint(&[0; MULTISIG_LEN], &TOKEN_PROGRAM_ID), None);
+
+        assert_eq!(is_mint(&mint, &TOKEN_2022_PROGRAM_ID), Some(true));
+        account[TOKEN_ACCOUNT_LEN] = 2;
+        assert_eq!(is_mint(&account, &TOKEN_2022_PROGRAM_ID), Some(false));
+        let mut extended_mint = [0u8; TOKEN_ACCOUNT_LEN + 2];
+        extended_mint[..MINT_LEN].copy_from_slice(&mint);
+        extended_mint[TOKEN_ACCOUNT_LEN] = 1;
+        assert_eq!(is_mint(&extended_mint, &TOKEN_2022_PROGRAM_ID), Some(true));
+        extended_mint[TOKEN_ACCOUNT_LEN] = 0;
+        assert_eq!(is_mint(&extended_mint, &TOKEN_2022_PROGRAM_ID), None);
+        // a multisig's byte 165 is part of its signer list
+        let mut multisig = [0u8; MULTISIG_LEN];
+        multisig[TOKEN_ACCOUNT_LEN] = 1;
+        assert_eq!(is_mint(&multisig, &TOKEN_2022_PROGRAM_ID), None);
+
+        assert_eq!(is_mint(&mint, &SYSTEM_PROGRAM_ID), None);
+    }
+
+    const MULTIPLE_ACCOUNTS: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":9},"value":[
+        {"lamports":1,"owner":"11111111111111111111111111111111","data":["AQID","base64"],"executable":false,"rentEpoch":0,"space":3},
+        null,
+        {"lamports":2,"owner":"11111111111111111111111111111111","data":["BAUG","base64"],"executable":false,"rentEpoch":0,"space":3},
+        {"lamports":3,"owner":"11111111111111111111111111111111","data":["Bw==","base64"],"executable":false,"rentEpoch":0,"space":1}
+    ]}}"#;
+
+    /// Account data of the next item; errors are compared by presence only.
+    fn account_data<'buf>(
+        accounts: &mut MultipleAccounts<'_, '_, 'buf>,
+    ) -> Option<core::result::Result<Option<&'buf [u8]>, ()>> {
+        accounts.next().map(|account| {
+            account
+                .map(|account| account.map(|account| account.data))
+                .map_err(|_| ())
+        })
+    }
+
+    #[test]
+    fn multiple_accounts_share_an_arena() {
+        let rpc = RpcClient::new_async(
+            "",
+            Commitment::Confirmed,
+            MockClient::new(MULTIPLE_ACCOUNTS),
+        );
+        let addresses = [Address::new([1; 32]); 4];
+        let mut arena = [0u8; 4];
+        let mut resp_buffer = [0u8; 1024];
+        let accounts = block_on(rpc.get_multiple_accounts(
+            &addresses,
+            AccountInfoConfig::default(),
+            DataBuffers::Arena(&mut arena),
+            &mut resp_buffer,
+        ))
+        .unwrap();
+        assert_eq!(accounts.slot, 9);
+        let mut accounts = accounts.value;
+        assert_eq!(account_data(&mut accounts), Some(Ok(Some(&[1, 2, 3][..]))));
+        assert_eq!(account_data(&mut accounts), Some(Ok(None)));
+        // one byte left: the second account does not fit but the third does
+        assert_eq!(account_data(&mut accounts), Some(Err(())));
+        assert_eq!(account_data(&mut accounts), Some(Ok(Some(&[7][..]))));
+        assert!(accounts.next().is_none());
+    }
+
+    #[test]
+    fn multiple_accounts_fill_per_account_buffers() {
+        let rpc = RpcClient::new_async(
+            "",
+            Commitment::Confirmed,
+            MockClient::new(MULTIPLE_ACCOUNTS),
+        );
+        let addresses = [Address::new([1; 32]); 4];
+        let (mut first, mut second, mut third, mut fourth) =
+            ([0u8; 3], [0u8; 0], [0u8; 2], [0u8; 1]);
+        let mut buffers = [
+            &mut first[..],
+            &mut second[..],
+            &mut third[..],
+            &mut fourth[..],
+        ];
+        let mut resp_buffer = [0u8; 1024];
+        let mut accounts = block_on(rpc.get_multiple_accounts(
+            &addresses,
+            AccountInfoConfig::default(),
+            DataBuffers::PerAccount(&mut buffers),
+            &mut resp_buffer,
+        ))
+        .unwrap()
+        .value;
+        assert_eq!(account_data(&mut accounts), Some(Ok(Some(&[1, 2, 3][..]))));
+        assert_eq!(account_data(&mut accounts), Some(Ok(None)));
+        assert_eq!(account_data(&mut accounts), Some(Err(())));
+        assert_eq!(account_data(&mut accounts), Some(Ok(Some(&[7][..]))));
+
+        let mut too_few = [&mut first[..]];
+        let result = block_on(rpc.get_multiple_accounts(
+            &addresses,
+            AccountInfoConfig::default(),
+            DataBuffers::PerAccount(&mut too_few),
+            &mut resp_buffer,
+        ));
+        assert!(matches!(result, Err(SdkError::Invalid)));
+    }
+
+    #[test]
+    fn multiple_accounts_reject_oversized_requests() {
+        let rpc = RpcClient::new_async(
+            "",
+            Commitment::Confirmed,
+            MockClient::new(MULTIPLE_ACCOUNTS),
+        );
+        let addresses = [Address::new([1; 32]); MAX_MULTIPLE_ACCOUNTS + 1];
+        let mut resp_buffer = [0u8; 1024];
+        let result = block_on(rpc.get_multiple_accounts(
+            &addresses,
+            AccountInfoConfig::default(),
+            DataBuffers::Arena(&mut []),
+            &mut resp_buffer,
+        ));
+        assert!(matches!(result, Err(SdkError::Invalid)));
+        assert_eq!(rpc.client.sent("getMultipleAccounts"), 0);
+    }
+
+    fn rpc_error(body: &[u8]) -> RpcError {
+        let body = Value::parse(body).unwrap();
+        parse_rpc_error(body.get("error").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_rpc_errors() {
+        let error = rpc_error(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"Transaction simulation failed: Blockhash not found","data":{"accounts":null,"err":"BlockhashNotFound","logs":[],"unitsConsumed":0}}}"#);
+        assert_eq!(error.code, -32002);
+        assert_eq!(error.kind, RpcErrorKind::BlockhashNotFound);
+        assert_eq!(
+            error.transaction_error,
+            Some(TransactionError::BlockhashNotFound)
+        );
+        assert_eq!(
+            error.message.as_str(),
+            "Transaction simulation failed: Blockhash not found"
+        );
+
+        let error = rpc_error(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"Transaction simulation failed: Error processing Instruction 0: custom program error: 0x1","data":{"err":{"InstructionError":[0,{"Custom":1}]},"logs":[]}}}"#);
+        assert_eq!(error.kind, RpcErrorKind::SendTransactionPreflightFailure);
+        assert_eq!(
+            error.transaction_error,
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            ))
+        );
+        // the message is cut at MAX_RPC_ERROR_MESSAGE_LEN bytes
+        assert_eq!(
+            error.message.as_str(),
+            "Transaction simulation failed: Error processing Instruction 0: c"
+        );
+
+        let error = rpc_error(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32005,"message":"Node is unhealthy","data":{}}}"#);
+        assert_eq!(error.kind, RpcErrorKind::NodeUnhealthy);
+        assert_eq!(error.transaction_error, None);
+        assert_eq!(error.message.as_str(), "Node is unhealthy");
+
+        // a message cut inside a multi-byte character drops the whole character
+        let error = rpc_error(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32603,"message":"\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9\u00e9!\u00e9"}}"#);
+        assert_eq!(error.kind, RpcErrorKind::InternalError);
+        assert_eq!(error.message.len(), 63);
+        assert!(error.message.ends_with('!'));
+
+        let error = rpc_error(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-1,"message":null}}"#);
+        assert_eq!(error.kind, RpcErrorKind::Other);
+        assert!(error.message.is_empty());
+    }
+
+    fn system_instruction(tag: u32, fields: &[&[u8]]) -> Vec<u8> {
+        let mut data = tag.to_le_bytes().to_vec();
+        for field in fields {
+            data.extend_from_slice(field);
+        }
+        data
+    }
+
+    #[test]
+    fn funding_counts_rent_and_payer_transfers_in_one_batch() {
+        const FUNDING: &[u8] = br#"[
+            {"jsonrpc":"2.0","result":{"context":{"slot":1},"value":3000000},"id":1},
+            {"jsonrpc":"2.0","result":{"context":{"slot":1},"value":5000},"id":2},
+            {"jsonrpc":"2.0","result":2039280,"id":3},
+            {"jsonrpc":"2.0","result":1461600,"id":4}
+        ]"#;
+        let payer = Keypair::new_from_seed([1; 32]);
+        let funder = Keypair::new_from_seed([2; 32]);
+        let (first, second, seeded, recipient) = (
+            Address::new([3; 32]),
+            Address::new([4; 32]),
+            Address::new([5; 32]),
+            Address::new([6; 32]),
+        );
+        let owner = [7u8; 32];
+        // below the rent-exempt minimum, so counted at the minimum
+        let create =
+            system_instruction(0, &[&1_000u64.to_le_bytes(), &165u64.to_le_bytes(), &owner]);
+        let create_with_seed = system_instruction(
+            3,
+            &[
+                payer.public_key().as_ref(),
+                &3u64.to_le_bytes(),
+                b"abc",
+                &2_000_000u64.to_le_bytes(),
+                &82u64.to_le_bytes(),
+                &owner,
+            ],
+        );
+        let transfer = system_instruction(2, &[&500u64.to_le_bytes()]);
+        let funded_create = system_instruction(
+            0,
+            &[&2_039_280u64.to_le_bytes(), &165u64.to_le_bytes(), &owner],
+        );
+        let create_metas = [
+            AccountMeta::new_writable(payer.public_key(), true),
+            AccountMeta::new_writable(&first, true),
+        ];
+        let seed_metas = [
+            AccountMeta::new_writable(payer.public_key(), true),
+            AccountMeta::new_writable(&seeded, false),
+        ];
+        let transfer_metas = [
+            AccountMeta::new_writable(payer.public_key(), true),
+            AccountMeta::new_writable(&recipient, false),
+        ];
+        let funded_metas = [
+            AccountMeta::new_writable(funder.public_key(), true),
+            AccountMeta::new_writable(&second, true),
+        ];
+        let instruction = |data, accounts| Instruction {
+            program_id: &SYSTEM_PROGRAM_ID,
+            data,
+            accounts,
+        };
+        let instructions = [
+            instruction(&create[..], &create_metas[..]),
+            instruction(&create_with_seed[..], &seed_metas[..]),
+            instruction(&transfer[..], &transfer_metas[..]),
+            instruction(&funded_create[..], &funded_metas[..]),
+        ];
+        let transaction = Transaction {
+            signers: &[&payer, &funder],
+            instructions: &instructions,
+            recent_blockhash: &Hash::default(),
+        };
+        let message = transaction.compile_message().unwrap();
+
+        let rpc = RpcClient::new_async("", Commitment::Confirmed, MockClient::new(FUNDING));
+        let report = block_on(rpc.check_funding(&message)).unwrap();
+        assert_eq!(
+            report,
+            FundingReport {
+                balance: 3_000_000,
+                fee: 5_000,
+                rent: 2 * 2_039_280 + 1_461_600,
+                transfers: 2_039_280 + 2_000_000 + 500,
+                shortfall: 5_000 + 2_039_280 + 2_000_000 + 500 - 3_000_000,
+            }
+        );
+        assert!(!report.is_funded());
+        let requests = rpc.client.requests.borrow();
+        assert_eq!(requests.len(), 1);
+        // both 165-byte accounts share one lookup
+        assert_eq!(
+            requests[0]
+                .matches("getMinimumBalanceForRentExemption")
+                .count(),
+            2
+        );
+    }
+
+    /// Message of the transaction in a recorded `sendTransaction` body.
+    fn sent_message(request: &str) -> Vec<u8> {
+        let start = request.find(r#""params":[""#).unwrap() + 11;
+        let encoded = &request[start..];
+        let encoded = &encoded[..encoded.find('"').unwrap()];
+        let mut bytes = [0u8; PACKET_DATA_SIZE];
+        let len = STANDARD.decode_slice(encoded, &mut bytes).unwrap();
+        TransactionView::parse(&bytes[..len])
+            .unwrap()
+            .message_bytes()
+            .to_vec()
+    }
+
+    #[test]
+    fn send_config_sets_compute_unit_price() {
+        const SENT: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":"1111111111111111111111111111111111111111111111111111111111111111"}"#;
+        let payer = Keypair::new_from_seed([1; 32]);
+        let metas = [AccountMeta::new_writable(payer.public_key(), true)];
+        let old_price = set_compute_unit_price_data(1);
+        let instructions = [
+            compute_budget_instruction(&old_price),
+            Instruction {
+                program_id: &SYSTEM_PROGRAM_ID,
+                data: &[],
+                accounts: &metas,
+            },
+        ];
+        let transaction = Transaction {
+            signers: &[&payer],
+            instructions: &instructions,
+            recent_blockhash: &Hash::default(),
+        };
+        let rpc = RpcClient::new_async("", Commitment::Confirmed, MockClient::new(SENT));
+        let config = SendTransactionConfig {
+            compute_unit_price: Some(5_000),
+            ..Default::default()
+        };
+        block_on(rpc.send_transaction_with_config(&transaction, config)).unwrap();
+        block_on(rpc.send_transaction(&transaction)).unwrap();
+
+        let requests = rpc.client.requests.borrow();
+        let priced = sent_message(&requests[0]);
+        let priced = CompiledMessage::parse(&priced).unwrap();
+        let budget: Vec<_> = priced
+            .instructions()
+            .filter(|instruction| {
+                priced.account_key(instruction.program_id_index as usize)
+                    == Some(&COMPUTE_BUDGET_PROGRAM_ID)
+            })
+            .map(|instruction| instruction.data)
+            .collect();
+        // the transaction's own price is replaced, not duplicated
+        assert_eq!(budget, [&set_compute_unit_price_data(5_000)[..]]);
+        assert_eq!(priced.instructions().count(), 2);
+        assert_eq!(
+            sent_message(&requests[1]),
+            transaction.compile_message().unwrap().as_slice()
+        );
+    }
+
+    #[test]
+    fn sizes_and_prices_from_simulation() {
+        const SIMULATED: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":7},"value":{"err":null,"logs":["Program log: ok"],"accounts":null,"unitsConsumed":1000,"returnData":null}}}"#;
+        const FEES: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":[{"slot":1,"prioritizationFee":0},{"slot":2,"prioritizationFee":2000},{"slot":3,"prioritizationFee":500}]}"#;
+        const SENT: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":"1111111111111111111111111111111111111111111111111111111111111111"}"#;
+        let payer = Keypair::new_from_seed([1; 32]);
+        let metas = [AccountMeta::new_writable(payer.public_key(), true)];
+        let old_limit = set_compute_unit_limit_data(200_000);
+        let instructions = [
+            compute_budget_instruction(&old_limit),
+            Instruction {
+                program_id: &SYSTEM_PROGRAM_ID,
+                data: &[],
+                accounts: &metas,
+            },
+        ];
+        let transaction = Transaction {
+            signers: &[&payer],
+            instructions: &instructions,
+            recent_blockhash: &Hash::default(),
+        };
+        let rpc = RpcClient::new_async(
+            "",
+            Commitment::Confirmed,
+            MockClient::with(&[
+                ("simulateTransaction", &[SIMULATED]),
+                ("getRecentPrioritizationFees", &[FEES]),
+                ("sendTransaction", &[SENT]),
+            ]),
+        );
+        let config = ComputeUnitConfig {
+            margin_percent: 20,
+            priority_fee: Some(PriorityFeeEstimator {
+                percentile: 50,
+                ..Default::default()
+            }),
+        };
+        let mut buffer = [0u8; 2048];
+        let (_, applied) = block_on(rpc.send_with_compute_unit_limit(
+            &transaction,
+            config,
+            SendTransactionConfig::default(),
+            &mut buffer,
+        ))
+        .unwrap();
+        assert_eq!(
+            applied,
+            AppliedComputeBudget {
+                unit_limit: 1_200,
+                unit_price: Some(500),
+            }
+        );
+
+        let requests = rpc.client.requests.borrow();
+        // simulated at the maximum limit, replacing the transaction's own
+        let simulated = sent_message(&requests[0]);
+        let simulated = CompiledMessage::parse(&simulated).unwrap();
+        let data: Vec<_> = simulated.instructions().map(|i| i.data).collect();
+        assert_eq!(
+            data,
+            [
+                &set_compute_unit_limit_data(MAX_COMPUTE_UNIT_LIMIT)[..],
+                &[]
+            ]
+        );
+        assert!(requests[1].contains(&format!(r#"["{}"]"#, payer.public_key())));
+        let sent = sent_message(&requests[2]);
+        let sent = CompiledMessage::parse(&sent).unwrap();
+        let data: Vec<_> = sent.instructions().map(|i| i.data).collect();
+        assert_eq!(
+            data,
+            [
+                &set_compute_unit_limit_data(1_200)[..],
+                &set_compute_unit_price_data(500)[..],
+                &[],
+            ]
+        );
+    }
+
+    const MAINNET_GENESIS: &[u8] =
+        br#"{"jsonrpc":"2.0","result":"5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d","id":1}"#;
+
+    #[test]
+    fn verifies_the_cluster_genesis_hash() {
+        let client = MockClient::new(MAINNET_GENESIS);
+        let rpc = block_on(RpcClient::new_verified(
+            Cluster::Mainnet,
+            Commitment::Confirmed,
+            client,
+        ))
+        .unwrap();
+        assert_eq!(rpc.client.sent("getGenesisHash"), 1);
+
+        let result = block_on(rpc.verify_cluster(&Cluster::Devnet));
+        assert!(matches!(
+            result,
+            Err(SdkError::WrongCluster(hash)) if hash == crate::cluster::MAINNET_GENESIS_HASH
+        ));
+        let result = block_on(RpcClient::new_verified(
+            Cluster::Testnet,
+            Commitment::Confirmed,
+            MockClient::new(MAINNET_GENESIS),
+        ));
+        assert!(matches!(result, Err(SdkError::WrongCluster(_))));
+        assert_eq!(rpc.client.sent("getGenesisHash"), 2);
+
+        let custom = Cluster::Custom {
+            url: "",
+            genesis_hash: Some(crate::cluster::MAINNET_GENESIS_HASH),
+        };
+        block_on(rpc.verify_cluster(&custom)).unwrap();
+        assert_eq!(rpc.client.sent("getGenesisHash"), 3);
+
+        // nothing to compare a local validator against
+        block_on(rpc.verify_cluster(&Cluster::Localnet)).unwrap();
+        assert_eq!(rpc.client.sent("getGenesisHash"), 3);
+    }
+
+    #[test]
+    fn reports_node_health() {
+        let health = |body: &'static [u8]| {
+            block_on(
+                RpcClient::new_async("", Commitment::Confirmed, MockClient::new(body)).get_health(),
+            )
+        };
+        assert_eq!(
+            health(br#"{"jsonrpc":"2.0","result":"ok","id":1}"#).unwrap(),
+            NodeHealth::Healthy
+        );
+        assert_eq!(
+            health(br#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Node is behind by 42 slots","data":{"numSlotsBehind":42}},"id":1}"#).unwrap(),
+            NodeHealth::Behind(Some(42))
+        );
+        assert_eq!(
+            health(br#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Node is unhealthy","data":{}},"id":1}"#).unwrap(),
+            NodeHealth::Behind(None)
+        );
+        assert_eq!(
+            health(br#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Node is unhealthy"},"id":1}"#).unwrap(),
+            NodeHealth::Behind(None)
+        );
+        assert!(matches!(
+            health(
+                br#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#
+            ),
+            Err(SdkError::RpcError(RpcError {
+                kind: RpcErrorKind::MethodNotFound,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            health(br#"{"jsonrpc":"2.0","result":"behind","id":1}"#),
+            Err(SdkError::ResponseParseError)
+        ));
+    }
+
+    #[test]
+    fn parses_node_versions() {
+        let rpc = RpcClient::new_async(
+            "",
+            Commitment::Confirmed,
+            MockClient::new(br#"{"jsonrpc":"2.0","result":{"feature-set":2891131721,"solana-core":"2.0.15"},"id":1}"#),
+        );
+        let version = block_on(rpc.get_version()).unwrap();
+        assert_eq!(version.solana_core.as_str(), "2.0.15");
+        assert_eq!(version.feature_set, Some(2891131721));
+        assert_eq!(version.semver(), Some((2, 0, 15)));
+
+        let semver = |core: &str| {
+            NodeVersion {
+                solana_core: heapless::String::try_from(core).unwrap(),
+                feature_set: None,
+            }
+            .semver()
+        };
+        assert_eq!(semver("2.2.0-beta.1"), Some((2, 2, 0)));
+        assert_eq!(semver("1.18.26+agave"), Some((1, 18, 26)));
+        assert_eq!(semver("2.0"), None);
+        assert_eq!(semver("2.0.15.1"), None);
+        assert_eq!(semver("2..15"), None);
+        assert_eq!(semver("v2.0.15"), None);
+        assert_eq!(semver("2.0.99999999999"), None);
+        assert_eq!(semver(""), None);
+    }
+
+    fn parsed(json: &[u8]) -> Option<ParsedAccount> {
+        ParsedAccount::from_json(Value::parse(json).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_json_token_accounts() {
+        let account = parsed(br#"{"program":"spl-token","parsed":{"info":{"isNative":false,"mint":"So11111111111111111111111111111111111111112","owner":"SysvarC1ock11111111111111111111111111111111","state":"frozen","tokenAmount":{"amount":"1500000","decimals":6,"uiAmount":1.5,"uiAmountString":"1.5"},"delegate":"Vote111111111111111111111111111111111111111","delegatedAmount":{"amount":"250","decimals":6,"uiAmount":0.00025,"uiAmountString":"0.00025"},"closeAuthority":"Stake11111111111111111111111111111111111111"},"type":"account"},"space":165}"#);
+        let Some(ParsedAccount::TokenAccount(account)) = account else {
+            panic!("{account:?}");
+        };
+        assert_eq!(
+            format!("{} {}", account.mint, account.owner),
+            "So11111111111111111111111111111111111111112 SysvarC1ock11111111111111111111111111111111"
+        );
+        assert_eq!(account.amount, 1_500_000);
+        assert_eq!(account.state, AccountState::Frozen);
+        assert_eq!(account.is_native, None);
+        assert_eq!(
+            format!("{}", account.delegate.unwrap()),
+            "Vote111111111111111111111111111111111111111"
+        );
+        assert_eq!(account.delegated_amount, 250);
+        assert_eq!(
+            format!("{}", account.close_authority.unwrap()),
+            "Stake11111111111111111111111111111111111111"
+        );
+
+        // nodes omit unset optional fields
+        let account = parsed(br#"{"program":"spl-token-2022","parsed":{"info":{"isNative":true,"mint":"So11111111111111111111111111111111111111112","owner":"SysvarC1ock11111111111111111111111111111111","rentExemptReserve":{"amount":"2039280","decimals":9,"uiAmount":0.00203928,"uiAmountString":"0.00203928"},"state":"initialized","tokenAmount":{"amount":"0","decimals":9,"uiAmount":0.0,"uiAmountString":"0"},"extensions":[{"extension":"immutableOwner"}]},"type":"account"},"space":170}"#);
+        let Some(ParsedAccount::TokenAccount(account)) = account else {
+            panic!("{account:?}");
+        };
+        assert_eq!(account.state, AccountState::Initialized);
+        assert_eq!(account.is_native, Some(2_039_280));
+        assert_eq!(account.delegate, None);
+        assert_eq!(account.delegated_amount, 0);
+        assert_eq!(account.close_authority, None);
+
+        let keyed: KeyedTokenAccount = Value::parse(br#"{"pubkey":"SysvarRent111111111111111111111111111111111","account":{"lamports":2039280,"owner":"TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA","data":{"program":"spl-token","parsed":{"info":{"isNative":false,"mint":"So11111111111111111111111111111111111111112","owner":"SysvarC1ock11111111111111111111111111111111","state":"initialized","tokenAmount":{"amount":"42","decimals":0,"uiAmount":42.0,"uiAmountString":"42"}},"type":"account"},"space":165},"executable":false,"rentEpoch":18446744073709551615,"space":165}}"#)
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(
+            format!("{}", keyed.pubkey),
+            "SysvarRent111111111111111111111111111111111"
+        );
+        assert_eq!(keyed.lamports, 2_039_280);
+        assert_eq!(keyed.account.amount, 42);
+        assert_eq!(keyed.account.delegate, None);
+    }
+
+    #[test]
+    fn parses_json_mints_and_nonces() {
+        let mint = parsed(br#"{"program":"spl-token","parsed":{"info":{"decimals":6,"freezeAuthority":null,"isInitialized":true,"mintAuthority":"Vote111111111111111111111111111111111111111","supply":"18446744073709551615"},"type":"mint"},"space":82}"#);
+        let Some(ParsedAccount::Mint(mint)) = mint else {
+            panic!("{mint:?}");
+        };
+        assert_eq!(mint.decimals, 6);
+        assert!(mint.is_initialized);
+        assert_eq!(mint.supply, u64::MAX);
+        assert_eq!(
+            format!("{}", mint.mint_authority.unwrap()),
+            "Vote111111111111111111111111111111111111111"
+        );
+        assert_eq!(mint.freeze_authority, None);
+
+        let nonce = parsed(br#"{"program":"nonce","parsed":{"type":"initialized","info":{"authority":"Stake11111111111111111111111111111111111111","blockhash":"4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY","feeCalculator":{"lamportsPerSignature":"5000"}}},"space":80}"#);
+        let Some(ParsedAccount::Nonce(nonce)) = nonce else {
+            panic!("{nonce:?}");
+        };
+        assert_eq!(
+            format!("{} {}", nonce.authority, nonce.durable_nonce),
+            "Stake11111111111111111111111111111111111111 4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"
+        );
+        assert_eq!(nonce.lamports_per_signature, 5_000);
+    }
+
+    #[test]
+    fn skips_unknown_json_account_types() {
+        assert_eq!(
+            parsed(br#"{"program":"spl-token","parsed":{"info":{"isInitialized":true,"numRequiredSigners":1,"numValidSigners":1,"signers":[]},"type":"multisig"},"space":355}"#),
+            None
+        );
+        assert_eq!(
+            parsed(br#"{"program":"nonce","parsed":{"type":"uninitialized"},"space":80}"#),
+            None
+        );
+        assert_eq!(
+            parsed(br#"{"program":"vote","parsed":{"info":{},"type":"vote"},"space":3762}"#),
+            None
+        );
+        // the type is required
+        assert!(ParsedAccount::from_json(
+            Value::parse(br#"{"program":"spl-token","parsed":{"info":{}},"space":82}"#).unwrap()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn batch_results_match_by_id() {
+        let body = br#"[
+            {"jsonrpc":"2.0","id":3,"bogus":1},
+            "not a response",
+            {"jsonrpc":"2.0","result":20,"id":2},
+            {"jsonrpc":"2.0","id":1,"error":{"code":-32602,"message":"invalid params"}},
+            {"jsonrpc":"2.0","id":3,"result":30}
+        ]"#;
+        let batch = BatchResponse {
+            items: Value::parse(body).unwrap(),
+        };
+        assert_eq!(batch.result(2).unwrap().as_u64(), Some(20));
+        assert_eq!(batch.result(3).unwrap().as_u64(), Some(30));
+        assert!(matches!(batch.result(1), Err(SdkError::RpcError(_))));
+        assert!(matches!(batch.result(4), Err(SdkError::ResponseParseError)));
+    }
+
+    #[test]
+    fn latest_blockhash_honors_config() {
+        let rpc =
+            RpcClient::new_async("", Commitment::Finalized, MockClient::new(LATEST_BLOCKHASH));
+        let latest = block_on(rpc.get_latest_blockhash(ContextConfig::default())).unwrap();
+        assert_eq!(
+            latest,
+            LatestBlockhash {
+                blockhash: crate::cluster::TESTNET_GENESIS_HASH,
+                last_valid_block_height: 450,
+                slot: 320,
+            }
+        );
+        let config = ContextConfig {
+            commitment: Some(Commitment::Processed),
+            min_context_slot: Some(300),
+        };
+        block_on(rpc.get_latest_blockhash(config)).unwrap();
+
+        let requests = rpc.client.requests.borrow();
+        assert!(
+            requests[0]
+                .contains(r#""method":"getLatestBlockhash","params":[{"commitment":"finalized"}]"#),
+            "{}",
+            requests[0]
+        );
+        assert!(
+            requests[1].contains(r#""params":[{"commitment":"processed","minContextSlot":300}]"#),
+            "{}",
+            requests[1]
+        );
+    }
+
+    #[test]
+    fn blockhash_cache_fetches_once() {
+        let rpc =
+            RpcClient::new_async("", Commitment::Confirmed, MockClient::new(LATEST_BLOCKHASH));
+        let mut cache = BlockhashCache::new(10);
+        let first = block_on(cache.get(&rpc, 0)).unwrap();
+        assert_eq!(first.last_valid_block_height, 450);
+        assert_eq!(rpc.client.sent("getLatestBlockhash"), 1);
+
+        // 139 blocks later the hash is still 11 blocks from expiry
+        block_on(cache.get(&rpc, 139 * MS_PER_BLOCK)).unwrap();
+        assert_eq!(rpc.client.sent("getLatestBlockhash"), 1);
+        block_on(cache.get(&rpc, 140 * MS_PER_BLOCK)).unwrap();
+        assert_eq!(rpc.client.sent("getLatestBlockhash"), 2);
+
+        cache.invalidate();
+        block_on(cache.get(&rpc, 140 * MS_PER_BLOCK)).unwrap();
+        assert_eq!(rpc.client.sent("getLatestBlockhash"), 3);
+        assert_eq!(rpc.client.requests.borrow().len(), 3);
+    }
+
+    const SIMULATION: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":7},"value":{"err":{"InstructionError":[0,{"Custom":1}]},"logs":["Program 11111111111111111111111111111111 invoke [1]","Program log: \"quoted\" ] [","Program 11111111111111111111111111111111 failed: custom program error: 0x1"],"accounts":null,"unitsConsumed":150,"returnData":{"programId":"11111111111111111111111111111111","data":["AQID","base64"]},"innerInstructions":null}}}"#;
+
+    #[test]
+    fn simulation_streams_logs() {
+        let payer = Keypair::new_from_seed([1; 32]);
+        let metas = [AccountMeta::new_writable(payer.public_key(), true)];
+        let instructions = [Instruction {
+            program_id: &SYSTEM_PROGRAM_ID,
+            data: &[],
+            accounts: &metas,
+        }];
+        let transaction = Transaction {
+            signers: &[&payer],
+            instructions: &instructions,
+            recent_blockhash: &Hash::default(),
+        };
+        let rpc = RpcClient::new_async("", Commitment::Confirmed, MockClient::new(SIMULATION));
+
+        let mut buffer = [0u8; 1024];
+        let mut log_buffer = [0u8; 96];
+        let mut logs: Vec<String> = Vec::new();
+        let result = block_on(rpc.simulate_transaction(
+            &transaction,
+            SimulateTransactionConfig::default(),
+            &mut buffer,
+            &mut log_buffer,
+            |line| logs.push(String::from_utf8(line.bytes().collect()).unwrap()),
+        ))
+        .unwrap();
+        assert_eq!(result.slot, 7);
+        let value = result.value;
+        assert_eq!(
+            value.err,
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1)
+            ))
+        );
+        assert_eq!(value.units_consumed, Some(150));
+        let return_data = value.return_data.unwrap();
+        assert_eq!(return_data.program_id, SYSTEM_PROGRAM_ID);
+        assert_eq!(return_data.decode(&mut [0u8; 8]).unwrap(), [1, 2, 3]);
+        assert!(value.accounts.is_none() && value.inner_instructions.is_none());
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[1], r#"Program log: "quoted" ] ["#);
+
+        // only the request and the result outside the logs have to fit
+        let request_len = rpc.client.requests.borrow()[0].len();
+        let logs_len = SIMULATION.windows(5).position(|w| w == b"],\"ac").unwrap()
+            - SIMULATION
+                .windows(6)
+                .position(|w| w == b"\"logs\"")
+                .unwrap();
+        let resp_len = 400;
+        assert!(SIMULATION.len() - logs_len < resp_len);
+        assert!(SIMULATION.len() > resp_len);
+        let mut buffer = vec![0u8; request_len + resp_len];
+        let result = block_on(rpc.simulate_transaction(
+            &transaction,
+            SimulateTransactionConfig::default(),
+            &mut buffer,
+            &mut [],
+            |_| panic!("logs are skipped"),
+        ))
+        .unwrap();
+        assert_eq!(result.value.units_consumed, Some(150));
+
+        // a log line longer than its buffer fails instead of truncating
+        let mut buffer = [0u8; 1024];
+        let result = block_on(rpc.simulate_transaction(
+            &transaction,
+            SimulateTransactionConfig::default(),
+            &mut buffer,
+            &mut [0u8; 16],
+            |_| {},
+        ));
+        assert!(matches!(result, Err(SdkError::ResponseParseError)));
+
+        let request = &rpc.client.requests.borrow()[0];
+        assert!(request.contains(r#""sigVerify":false,"replaceRecentBlockhash":false"#));
+    }
+
+    const UNSEEN: &[u8] =
+        br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":5},"value":[null]}}"#;
+    const PROCESSED: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":5},"value":[{"slot":4,"confirmations":0,"err":null,"status":{"Ok":null},"confirmationStatus":"processed"}]}}"#;
+    const CONFIRMED: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":6},"value":[{"slot":4,"confirmations":1,"err":null,"status":{"Ok":null},"confirmationStatus":"confirmed"}]}}"#;
+    const FAILED: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":{"context":{"slot":6},"value":[{"slot":4,"confirmations":1,"err":{"InstructionError":[0,{"Custom":1}]},"status":{"Err":{"InstructionError":[0,{"Custom":1}]}},"confirmationStatus":"confirmed"}]}}"#;
+    const HEIGHT_100: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":100}"#;
+    const HEIGHT_201: &[u8] = br#"{"jsonrpc":"2.0","id":1,"result":201}"#;
+
+    /// Local clock advanced by sleeping and by `latency_ms` on every read,
+    /// standing in for RPC round trips.
+    struct TestClock {
+        now: core::cell::Cell<u64>,
+        slept: core::cell::Cell<u64>,
+        latency_ms: u64,
+    }
+
+    impl TestClock {
+        fn new(latency_ms: u64) -> Self {
+            TestClock {
+                now: core::cell::Cell::new(0),
+                slept: core::cell::Cell::new(0),
+                latency_ms,
+            }
+        }
+
+        fn now_ms(&self) -> u64 {
+            self.now.set(self.now.get() + self.latency_ms);
+            self.now.get()
+        }
+    }
+
+    impl Sleep for TestClock {
+        async fn sleep_ms(&self, ms: u32) {
+            self.now.set(self.now.get() + ms as u64);
+            self.slept.set(self.slept.get() + ms as u64);
+        }
+    }
+
+    fn confirm(
+        responses: &[(&'static str, &[&'static [u8]])],
+        clock: &TestClock,
+    ) -> (Result<TransactionOutcome>, MockClient) {
+        let rpc = RpcClient::new_async("", Commitment::Confirmed, MockClient::with(responses));
+        let config = ConfirmConfig {
+            commitment: None,
+            poll_interval_ms: 1_000,
+            timeout_ms: 10_000,
+        };
+        let outcome = block_on(rpc.confirm_transaction(
+            &Signature::from([1; 64]),
+            200,
+            config,
+            clock,
+            || clock.now_ms(),
+        ));
+        (outcome, rpc.client)
+    }
+
+    #[test]
+    fn confirms_landed_transaction() {
+        let clock = TestClock::new(0);
+        let (outcome, client) = confirm(
+            &[
+                ("getBlockHeight", &[HEIGHT_100]),
+                ("getSignatureStatuses", &[UNSEEN, PROCESSED, CONFIRMED]),
+            ],
+            &clock,
+        );
+        assert_eq!(outcome.unwrap(), TransactionOutcome::Landed { slot: 4 });
+        assert_eq!(client.sent("getSignatureStatuses"), 3);
+        assert_eq!(clock.slept.get(), 2_000);
+    }
+
+    #[test]
+    fn confirms_failed_transaction() {
+        let clock = TestClock::new(0);
+        let (outcome, _) = confirm(
+            &[
+                ("getBlockHeight", &[HEIGHT_100]),
+                ("getSignatureStatuses", &[PROCESSED, FAILED]),
+            ],
+            &clock,
+        );
+        assert_eq!(
+            outcome.unwrap(),
+            TransactionOutcome::Failed {
+                slot: Some(4),
+                error: TransactionError::InstructionError(0, InstructionError::Custom(1)),
+            }
+        );
+    }
+
+    #[test]
+    fn confirms_expired_transaction() {
+        // expiry is checked for seen transactions too, not only unseen ones
+        for statuses in [UNSEEN, PROCESSED] {
+            let clock = TestClock::new(0);
+            let (outcome, client) = confirm(
+                &[
+                    ("getBlockHeight", &[HEIGHT_100, HEIGHT_100, HEIGHT_201]),
+                    ("getSignatureStatuses", &[statuses]),
+                ],
+                &clock,
+            );
+            assert_eq!(outcome.unwrap(), TransactionOutcome::Expired);
+            assert_eq!(client.sent("getSignatureStatuses"), 3);
+        }
+
+        // landing in the same poll the blockhash expires still counts
+        let clock = TestClock::new(0);
+        let (outcome, _) = confirm(
+            &[
+                ("getBlockHeight", &[HEIGHT_201]),
+                ("getSignatureStatuses", &[CONFIRMED]),
+            ],
+            &clock,
+        );
+        assert_eq!(outcome.unwrap(), TransactionOutcome::Landed { slot: 4 });
+    }
+
+    #[test]
+    fn confirm_times_out_on_the_callers_clock() {
+        let clock = TestClock::new(0);
+        let (outcome, client) = confirm(
+            &[
+                ("getBlockHeight", &[HEIGHT_100]),
+                ("getSignatureStatuses", &[UNSEEN]),
+            ],
+            &clock,
+        );
+        assert!(matches!(outcome, Err(SdkError::Timeout)));
+        assert_eq!(clock.slept.get(), 10_000);
+        assert_eq!(client.sent("getSignatureStatuses"), 11);
+
+        // slow round trips eat into the timeout instead of extending it
+        let clock = TestClock::new(750);
+        let (outcome, client) = confirm(
+            &[
+                ("getBlockHeight", &[HEIGHT_100]),
+                ("getSignatureStatuses", &[PROCESSED]),
+            ],
+            &clock,
+        );
+        assert!(matches!(outcome, Err(SdkError::Timeout)));
+        // the clock starts at one read and ends one read past the deadline
+        assert!(clock.now.get() <= 10_000 + 2 * 750, "{}", clock.now.get());
+        assert!(clock.slept.get() < 10_000);
+        assert!(client.sent("getSignatureStatuses") < 11);
+    }
+
+    #[test]
+    fn preflight_failure_reports_the_payers_signature() {
+        const PREFLIGHT: &[u8] = br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32002,"message":"Transaction simulation failed: Error processing Instruction 0: custom program error: 0x1","data":{"err":{"InstructionError":[0,{"Custom":1}]},"logs":[]}}}"#;
+        let payer = Keypair::new_from_seed([1; 32]);
+        let metas = [AccountMeta::new_writable(payer.public_key(), true)];
+        let instructions = [Instruction {
+            program_id: &SYSTEM_PROGRAM_ID,
+            data: &[],
+            accounts: &metas,
+        }];
+        let transaction = Transaction {
+            signers: &[&payer],
+            instructions: &instructions,
+            recent_blockhash: &Hash::default(),
+        };
+        let rpc = RpcClient::new_async(
+            "",
+            Commitment::Confirmed,
+            MockClient::with(&[("sendTransaction", &[PREFLIGHT])]),
+        );
+        let clock = TestClock::new(0);
+        let (signature, outcome) = block_on(rpc.send_and_confirm_transaction(
+            &transaction,
+            200,
+            SendTransactionConfig::default(),
+            ConfirmConfig::default(),
+            &clock,
+            || clock.now_ms(),
+        ))
+        .unwrap();
+        let message = transaction.compile_message().unwrap();
+        assert_eq!(
+            signature,
+            Signature::from(payer.sign_message(message.as_slice(), None))
+        );
+        assert_eq!(
+            outcome,
+            TransactionOutcome::Failed {
+                slot: None,
+                error: TransactionError::InstructionError(0, InstructionError::Custom(1)),
+            }
+        );
+        assert!(transaction_signature(&[1; 64]).is_err());
     }
 }
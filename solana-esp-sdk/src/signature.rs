@@ -8,6 +8,7 @@ pub const SIGNATURE_BYTES: usize = 64;
 /// Maximum string length of a base58 encoded signature
 const MAX_BASE58_SIGNATURE_LEN: usize = 88;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Signature(pub(crate) [u8; SIGNATURE_BYTES]);
 
 impl From<Signature> for [u8; 64] {
@@ -0,0 +1,517 @@
+use core::fmt::{self, Write};
+
+use crate::{
+    crypto::{Address, Keypair},
+    types::{Result, SdkError},
+};
+
+/// Maximum size of a rendered SIWS message.
+pub const MAX_MESSAGE_LEN: usize = 1024;
+/// Maximum number of `Resources` entries kept when parsing.
+pub const MAX_RESOURCES: usize = 8;
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Solana account:";
+
+/// Sign-In With Solana message, borrowing its fields from the caller or from
+/// the text it was parsed from.
+///
+/// Text layout:
+/// ```text
+/// ${domain} wants you to sign in with your Solana account:
+/// ${address}
+///
+/// ${statement}
+///
+/// URI: ${uri}
+/// Version: ${version}
+/// Chain ID: ${chain_id}
+/// Nonce: ${nonce}
+/// Issued At: ${issued_at}
+/// Expiration Time: ${expiration_time}
+/// Not Before: ${not_before}
+/// Request ID: ${request_id}
+/// Resources:
+/// - ${resources[0]}
+/// ```
+#[derive(Debug, Clone)]
+pub struct SiwsMessage<'a> {
+    pub domain: &'a str,
+    pub address: Address,
+    pub statement: Option<&'a str>,
+    pub uri: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub chain_id: Option<&'a str>,
+    pub nonce: Option<&'a str>,
+    /// RFC 3339 timestamp.
+    pub issued_at: Option<&'a str>,
+    /// RFC 3339 timestamp.
+    pub expiration_time: Option<&'a str>,
+    /// RFC 3339 timestamp.
+    pub not_before: Option<&'a str>,
+    pub request_id: Option<&'a str>,
+    pub resources: heapless::Vec<&'a str, MAX_RESOURCES>,
+}
+
+impl<'a> SiwsMessage<'a> {
+    pub fn new(domain: &'a str, address: Address) -> Self {
+        Self {
+            domain,
+            address,
+            statement: None,
+            uri: None,
+            version: None,
+            chain_id: None,
+            nonce: None,
+            issued_at: None,
+            expiration_time: None,
+            not_before: None,
+            request_id: None,
+            resources: heapless::Vec::new(),
+        }
+    }
+
+    /// Renders the canonical message text.
+    pub fn to_text(&self) -> Result<heapless::String<MAX_MESSAGE_LEN>> {
+        let mut text = heapless::String::new();
+        write!(text, "{}", self).map_err(|_| SdkError::Serialize)?;
+        Ok(text)
+    }
+
+    /// Parses a message in canonical form. Text that parses but does not
+    /// render back to the same bytes is rejected, so a signature over the
+    /// input is also a signature over `to_text()`.
+    pub fn parse(text: &'a str) -> Result<Self> {
+        let mut lines = text.split('\n').peekable();
+
+        let domain = lines
+            .next()
+            .and_then(|l| l.strip_suffix(HEADER_SUFFIX))
+            .filter(|d| !d.is_empty())
+            .ok_or(SdkError::Deserialize)?;
+        let address = lines
+            .next()
+            .and_then(|l| l.parse::<Address>().ok())
+            .ok_or(SdkError::Deserialize)?;
+        let mut message = SiwsMessage::new(domain, address);
+
+        if lines.next_if_eq(&"").is_some() {
+            let line = lines.next().ok_or(SdkError::Deserialize)?;
+            if is_field(line) {
+                message.parse_fields(line, &mut lines)?;
+            } else {
+                message.statement = Some(line);
+                if lines.next_if_eq(&"").is_some() {
+                    let line = lines.next().ok_or(SdkError::Deserialize)?;
+                    message.parse_fields(line, &mut lines)?;
+                }
+            }
+        }
+        if lines.next().is_some() {
+            return Err(SdkError::Deserialize);
+        }
+
+        if message.to_text()? != text {
+            return Err(SdkError::Invalid);
+        }
+        Ok(message)
+    }
+
+    fn parse_fields(
+        &mut self,
+        first: &'a str,
+        lines: &mut core::iter::Peekable<core::str::Split<'a, char>>,
+    ) -> Result<()> {
+        let mut line = Some(first);
+        macro_rules! field {
+            ($prefix:literal, $slot:expr) => {
+                if let Some(value) = line.and_then(|l| l.strip_prefix($prefix)) {
+                    $slot = Some(value);
+                    line = lines.next();
+                }
+            };
+        }
+        field!("URI: ", self.uri);
+        field!("Version: ", self.version);
+        field!("Chain ID: ", self.chain_id);
+        field!("Nonce: ", self.nonce);
+        field!("Issued At: ", self.issued_at);
+        field!("Expiration Time: ", self.expiration_time);
+        field!("Not Before: ", self.not_before);
+        field!("Request ID: ", self.request_id);
+        if line == Some("Resources:") {
+            while let Some(resource) = lines.peek().and_then(|l| l.strip_prefix("- ")) {
+                self.resources
+                    .push(resource)
+                    .map_err(|_| SdkError::Unsupported)?;
+                lines.next();
+            }
+            line = None;
+        }
+        match line {
+            None => Ok(()),
+            Some(_) => Err(SdkError::Deserialize),
+        }
+    }
+
+    /// Checks the message against what the verifier expects: the domain it
+    /// serves, the nonce it issued and the current Unix time, if known. With
+    /// a time, messages issued in the future, expired or not yet valid are
+    /// rejected.
+    pub fn validate(&self, domain: &str, nonce: Option<&str>, now: Option<i64>) -> Result<()> {
+        if self.domain != domain {
+            return Err(SdkError::Invalid);
+        }
+        if nonce.is_some() && self.nonce != nonce {
+            return Err(SdkError::Invalid);
+        }
+        let Some(now) = now else {
+            return Ok(());
+        };
+        if let Some(expiration_time) = self.expiration_time {
+            if now >= parse_rfc3339(expiration_time).ok_or(SdkError::Invalid)? {
+                return Err(SdkError::Invalid);
+            }
+        }
+        if let Some(not_before) = self.not_before {
+            if now < parse_rfc3339(not_before).ok_or(SdkError::Invalid)? {
+                return Err(SdkError::Invalid);
+            }
+        }
+        if let Some(issued_at) = self.issued_at {
+            if now < parse_rfc3339(issued_at).ok_or(SdkError::Invalid)? {
+                return Err(SdkError::Invalid);
+            }
+        }
+        Ok(())
+    }
+
+    /// Signs the message text. `keypair` must own `address`.
+    pub fn sign(&self, keypair: &Keypair) -> Result<[u8; 64]> {
+        if keypair.public_key() != &self.address {
+            return Err(SdkError::Invalid);
+        }
+        let text = self.to_text()?;
+        Ok(keypair.sign_message(text.as_bytes(), None))
+    }
+
+    /// Verifies `signature` over the message text against `address`.
+    pub fn verify(&self, signature: &[u8; 64]) -> Result<bool> {
+        let text = self.to_text()?;
+        Ok(self.address.verify_signature(text.as_bytes(), signature))
+    }
+}
+
+impl fmt::Display for SiwsMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}\n{}", self.domain, HEADER_SUFFIX, self.address)?;
+        if let Some(statement) = self.statement {
+            write!(f, "\n\n{}", statement)?;
+        }
+
+        let fields = [
+            ("URI", self.uri),
+            ("Version", self.version),
+            ("Chain ID", self.chain_id),
+            ("Nonce", self.nonce),
+            ("Issued At", self.issued_at),
+            ("Expiration Time", self.expiration_time),
+            ("Not Before", self.not_before),
+            ("Request ID", self.request_id),
+        ];
+        let mut separator = "\n\n";
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write!(f, "{}{}: {}", separator, name, value)?;
+                separator = "\n";
+            }
+        }
+        if !self.resources.is_empty() {
+            write!(f, "{}Resources:", separator)?;
+            for resource in self.resources.iter() {
+                write!(f, "\n- {}", resource)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_field(line: &str) -> bool {
+    const PREFIXES: [&str; 8] = [
+        "URI: ",
+        "Version: ",
+        "Chain ID: ",
+        "Nonce: ",
+        "Issued At: ",
+        "Expiration Time: ",
+        "Not Before: ",
+        "Request ID: ",
+    ];
+    line == "Resources:" || PREFIXES.iter().any(|p| line.starts_with(p))
+}
+
+/// Parses an RFC 3339 timestamp (`2024-01-01T00:00:00.000Z`, `...+02:00`)
+/// into Unix seconds.
+pub fn parse_rfc3339(s: &str) -> Option<i64> {
+    let b = s.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let num = |range: core::ops::Range<usize>| -> Option<i64> {
+        s.get(range)?.bytes().try_fold(0i64, |acc, c| {
+            c.is_ascii_digit().then(|| acc * 10 + (c - b'0') as i64)
+        })
+    };
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let month_days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    // 60 allows a leap second
+    if !(1..=month_days).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut pos = 19;
+    if b[pos] == b'.' {
+        pos += 1;
+        let digits = pos;
+        while pos < b.len() && b[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        if pos == digits {
+            return None;
+        }
+    }
+    let offset = match b.get(pos)? {
+        b'Z' | b'z' if pos + 1 == b.len() => 0,
+        sign @ (b'+' | b'-') if pos + 6 == b.len() && b[pos + 3] == b':' => {
+            let (hours, minutes) = (num(pos + 1..pos + 3)?, num(pos + 4..pos + 6)?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return None,
+    };
+
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    Some(days * 86400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = "example.com wants you to sign in with your Solana account:
+GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB
+
+Unlock the front door.
+
+URI: https://example.com/login
+Version: 1
+Chain ID: mainnet
+Nonce: 32891756
+Issued At: 2024-01-01T00:00:00Z
+Expiration Time: 2024-01-01T00:10:00.5+00:00
+Not Before: 2023-12-31T23:59:00Z
+Request ID: req-1
+Resources:
+- ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/
+- https://example.com/my-web2-claim.json";
+
+    fn keypair() -> Keypair {
+        Keypair::new_from_seed([7; 32])
+    }
+
+    #[test]
+    fn round_trips_every_field() {
+        let mut message = SiwsMessage::new("example.com", *keypair().public_key());
+        message.statement = Some("Unlock the front door.");
+        message.uri = Some("https://example.com/login");
+        message.version = Some("1");
+        message.chain_id = Some("mainnet");
+        message.nonce = Some("32891756");
+        message.issued_at = Some("2024-01-01T00:00:00Z");
+        message.expiration_time = Some("2024-01-01T00:10:00.5+00:00");
+        message.not_before = Some("2023-12-31T23:59:00Z");
+        message.request_id = Some("req-1");
+        message
+            .resources
+            .push("ipfs://bafybeiemxf5abjwjbikoz4mc3a3dla6ual3jsgpdr4cjr3oz3evfyavhwq/")
+            .unwrap();
+        message
+            .resources
+            .push("https://example.com/my-web2-claim.json")
+            .unwrap();
+        let text = message.to_text().unwrap();
+        assert_eq!(text.as_str(), FULL);
+
+        let parsed = SiwsMessage::parse(FULL).unwrap();
+        assert_eq!(parsed.to_text().unwrap(), text);
+        assert_eq!(parsed.request_id, Some("req-1"));
+        assert_eq!(parsed.resources.len(), 2);
+
+        let signature = parsed.sign(&keypair()).unwrap();
+        assert!(message.verify(&signature).unwrap());
+        assert!(parsed.sign(&Keypair::new_from_seed([8; 32])).is_err());
+    }
+
+    #[test]
+    fn round_trips_minimal_messages() {
+        let header = "example.com wants you to sign in with your Solana account:
+GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB";
+        let without_statement = alloc::format!("{header}\n\nNonce: 1");
+        let statement_only = alloc::format!("{header}\n\nHello");
+        for text in [header, &without_statement, &statement_only] {
+            let message = SiwsMessage::parse(text).unwrap();
+            assert_eq!(message.to_text().unwrap().as_str(), text);
+        }
+        assert_eq!(
+            SiwsMessage::parse(&without_statement).unwrap().nonce,
+            Some("1")
+        );
+        assert_eq!(
+            SiwsMessage::parse(&statement_only).unwrap().statement,
+            Some("Hello")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_messages() {
+        let replaced = |from: &str, to: &str| FULL.replacen(from, to, 1);
+        let bad = [
+            // fields out of order
+            replaced(
+                "Version: 1\nChain ID: mainnet",
+                "Chain ID: mainnet\nVersion: 1",
+            ),
+            replaced("URI: https://example.com/login\n", "").replacen(
+                "Request ID: req-1",
+                "Request ID: req-1\nURI: x",
+                1,
+            ),
+            // header and address
+            replaced(" wants you", " want you"),
+            replaced("example.com wants", " wants"),
+            replaced(
+                "GmaDrppBC7P5ARKV8g3djiwP89vz1jLK23V2GBjuAEGB",
+                "not-an-address",
+            ),
+            // blank lines and spacing
+            replaced("\n\nURI", "\nURI"),
+            replaced("\n\nURI", "\n\n\nURI"),
+            replaced("URI: ", "URI:"),
+            replaced("Nonce: ", "nonce: "),
+            // unknown or trailing lines
+            replaced("Request ID: req-1", "Request ID: req-1\nColor: blue"),
+            alloc::format!("{FULL}\n"),
+            replaced("- https", "* https"),
+            // non-canonical line endings
+            FULL.replace('\n', "\r\n"),
+        ];
+        for text in bad.iter() {
+            assert!(SiwsMessage::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_rfc3339() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2024-01-01T00:00:00Z"), Some(1_704_067_200));
+        assert_eq!(
+            parse_rfc3339("2024-01-01t00:00:00.123456z"),
+            Some(1_704_067_200)
+        );
+        assert_eq!(
+            parse_rfc3339("2024-01-01 02:30:00+02:30"),
+            Some(1_704_067_200)
+        );
+        assert_eq!(
+            parse_rfc3339("2023-12-31T20:00:00-04:00"),
+            Some(1_704_067_200)
+        );
+        assert_eq!(parse_rfc3339("2024-02-29T00:00:00Z"), Some(1_709_164_800));
+        assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z"), Some(951_782_400));
+        assert_eq!(parse_rfc3339("2016-12-31T23:59:60Z"), Some(1_483_228_800));
+        for bad in [
+            "2024-02-30T00:00:00Z",
+            "2024-02-31T00:00:00Z",
+            "2023-02-29T00:00:00Z",
+            "1900-02-29T00:00:00Z",
+            "2024-04-31T00:00:00Z",
+            "2024-13-01T00:00:00Z",
+            "2024-00-01T00:00:00Z",
+            "2024-01-00T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T00:60:00Z",
+            "2024-01-01T00:00:61Z",
+            "2024-01-01T00:00:00.Z",
+            "2024-01-01T00:00:00+24:00",
+            "2024-01-01T00:00:00+01:60",
+            "2024-01-01T00:00:00+0100",
+            "2024-01-01T00:00:00",
+            "2024-01-01T00:00:00Zx",
+            "2024-01-01X00:00:00Z",
+            "2024-1-01T00:00:00Z",
+            "+024-01-01T00:00:00Z",
+        ] {
+            assert_eq!(parse_rfc3339(bad), None, "{bad}");
+        }
+    }
+
+    #[test]
+    fn validates_time_bounds() {
+        let message = SiwsMessage::parse(FULL).unwrap();
+        let issued = parse_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        assert!(message
+            .validate("example.com", Some("32891756"), Some(issued))
+            .is_ok());
+        assert!(message
+            .validate("example.com", None, Some(issued + 599))
+            .is_ok());
+        assert!(message.validate("example.com", None, None).is_ok());
+
+        // expired, issued in the future, not yet valid
+        assert!(message
+            .validate("example.com", None, Some(issued + 600))
+            .is_err());
+        assert!(message
+            .validate("example.com", None, Some(issued - 1))
+            .is_err());
+        let mut early = message.clone();
+        early.issued_at = None;
+        assert!(early
+            .validate("example.com", None, Some(issued - 60))
+            .is_ok());
+        assert!(early
+            .validate("example.com", None, Some(issued - 61))
+            .is_err());
+
+        assert!(message.validate("evil.com", None, None).is_err());
+        assert!(message.validate("example.com", Some("1"), None).is_err());
+        let mut invalid = message.clone();
+        invalid.expiration_time = Some("2024-02-30T00:00:00Z");
+        assert!(invalid.validate("example.com", None, Some(issued)).is_err());
+    }
+}
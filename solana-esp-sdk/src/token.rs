@@ -0,0 +1,243 @@
+use core::fmt;
+
+use crate::{
+    crypto::Address,
+    types::{Result, SdkError},
+};
+
+/// Size of an SPL token account without Token-2022 extensions.
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Size of an SPL mint without Token-2022 extensions.
+pub const MINT_LEN: usize = 82;
+/// Size of an SPL multisig account, which Token-2022 never extends.
+pub const MULTISIG_LEN: usize = 355;
+
+/// Token amount in base units together with the mint's decimals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAmount {
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl TokenAmount {
+    pub fn new(amount: u64, decimals: u8) -> Self {
+        TokenAmount { amount, decimals }
+    }
+
+    /// Amount in whole tokens; may lose precision for large amounts.
+    pub fn ui_amount(&self) -> f64 {
+        let mut value = self.amount as f64;
+        for _ in 0..self.decimals {
+            value /= 10.0;
+        }
+        value
+    }
+}
+
+/// Formats the amount in whole tokens without trailing zeros, e.g. `1.5`.
+impl fmt::Display for TokenAmount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // u64::MAX has 20 digits
+        let mut digits = [b'0'; 20];
+        let mut len = 0;
+        let mut amount = self.amount;
+        while amount > 0 || len == 0 {
+            digits[19 - len] = b'0' + (amount % 10) as u8;
+            amount /= 10;
+            len += 1;
+        }
+        let digits = &digits[20 - len..];
+        let decimals = self.decimals as usize;
+        let (whole, fraction) = if len > decimals {
+            digits.split_at(len - decimals)
+        } else {
+            (&b"0"[..], digits)
+        };
+        // digits are ASCII
+        f.write_str(core::str::from_utf8(whole).map_err(|_| fmt::Error)?)?;
+        let significant = fraction.iter().rposition(|&d| d != b'0');
+        if let Some(last) = significant {
+            f.write_str(".")?;
+            for _ in len..decimals {
+                f.write_str("0")?;
+            }
+            f.write_str(core::str::from_utf8(&fraction[..=last]).map_err(|_| fmt::Error)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Lifecycle state of a token account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountState {
+    Uninitialized,
+    Initialized,
+    Frozen,
+}
+
+/// SPL Token or Token-2022 account; extensions are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenAccount {
+    pub mint: Address,
+    pub owner: Address,
+    /// Balance in base units.
+    pub amount: u64,
+    pub delegate: Option<Address>,
+    pub state: AccountState,
+    /// Rent-exempt reserve of a wrapped SOL account.
+    pub is_native: Option<u64>,
+    pub delegated_amount: u64,
+    pub close_authority: Option<Address>,
+}
+
+impl TokenAccount {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let data = data.get(..TOKEN_ACCOUNT_LEN).ok_or(SdkError::Deserialize)?;
+        Ok(TokenAccount {
+            mint: address(&data[0..32]),
+            owner: address(&data[32..64]),
+            amount: u64_le(&data[64..72]),
+            delegate: option(&data[72..108])?.map(address),
+            state: match data[108] {
+                0 => AccountState::Uninitialized,
+                1 => AccountState::Initialized,
+                2 => AccountState::Frozen,
+                _ => return Err(SdkError::Deserialize),
+            },
+            is_native: option(&data[109..121])?.map(u64_le),
+            delegated_amount: u64_le(&data[121..129]),
+            close_authority: option(&data[129..165])?.map(address),
+        })
+    }
+
+    /// Balance with the mint's `decimals`.
+    pub fn token_amount(&self, decimals: u8) -> TokenAmount {
+        TokenAmount::new(self.amount, decimals)
+    }
+}
+
+/// SPL Token or Token-2022 mint; extensions are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mint {
+    pub mint_authority: Option<Address>,
+    pub supply: u64,
+    pub decimals: u8,
+    pub is_initialized: bool,
+    pub freeze_authority: Option<Address>,
+}
+
+impl Mint {
+    pub fn unpack(data: &[u8]) -> Result<Self> {
+        let data = data.get(..MINT_LEN).ok_or(SdkError::Deserialize)?;
+        Ok(Mint {
+            mint_authority: option(&data[0..36])?.map(address),
+            supply: u64_le(&data[36..44]),
+            decimals: data[44],
+            is_initialized: data[45] != 0,
+            freeze_authority: option(&data[46..82])?.map(address),
+        })
+    }
+
+    pub fn token_amount(&self, amount: u64) -> TokenAmount {
+        TokenAmount::new(amount, self.decimals)
+    }
+}
+
+/// `COption`: a `u32` tag followed by the value.
+fn option(bytes: &[u8]) -> Result<Option<&[u8]>> {
+    match u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) {
+        0 => Ok(None),
+        1 => Ok(Some(&bytes[4..])),
+        _ => Err(SdkError::Deserialize),
+    }
+}
+
+fn address(bytes: &[u8]) -> Address {
+    let mut address = [0u8; 32];
+    address.copy_from_slice(bytes);
+    Address::new(address)
+}
+
+fn u64_le(bytes: &[u8]) -> u64 {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(bytes);
+    u64::from_le_bytes(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn formats_whole_tokens() {
+        assert_eq!(TokenAmount::new(5, 3).to_string(), "0.005");
+        assert_eq!(TokenAmount::new(1_500_000, 6).to_string(), "1.5");
+        assert_eq!(TokenAmount::new(0, 9).to_string(), "0");
+        assert_eq!(TokenAmount::new(42, 0).to_string(), "42");
+        assert_eq!(TokenAmount::new(2_000, 3).to_string(), "2");
+        assert_eq!(
+            TokenAmount::new(u64::MAX, 19).to_string(),
+            "1.8446744073709551615"
+        );
+    }
+
+    #[test]
+    fn converts_to_ui_amount() {
+        assert_eq!(TokenAmount::new(1_500_000, 6).ui_amount(), 1.5);
+        assert_eq!(TokenAmount::new(5, 3).ui_amount(), 0.005);
+        assert_eq!(TokenAmount::new(0, 9).ui_amount(), 0.0);
+        assert_eq!(TokenAmount::new(7, 0).ui_amount(), 7.0);
+    }
+
+    #[test]
+    fn unpacks_token_account() {
+        let mut data = [0u8; TOKEN_ACCOUNT_LEN];
+        data[0..32].fill(1);
+        data[32..64].fill(2);
+        data[64..72].copy_from_slice(&1_000u64.to_le_bytes());
+        data[72] = 1;
+        data[76..108].fill(3);
+        data[108] = 2;
+        data[109] = 1;
+        data[113..121].copy_from_slice(&2_039_280u64.to_le_bytes());
+        data[121..129].copy_from_slice(&250u64.to_le_bytes());
+
+        let account = TokenAccount::unpack(&data).unwrap();
+        assert_eq!(account.mint, Address::new([1; 32]));
+        assert_eq!(account.owner, Address::new([2; 32]));
+        assert_eq!(account.amount, 1_000);
+        assert_eq!(account.delegate, Some(Address::new([3; 32])));
+        assert_eq!(account.state, AccountState::Frozen);
+        assert_eq!(account.is_native, Some(2_039_280));
+        assert_eq!(account.delegated_amount, 250);
+        assert_eq!(account.close_authority, None);
+
+        assert!(TokenAccount::unpack(&data[..TOKEN_ACCOUNT_LEN - 1]).is_err());
+        data[108] = 3;
+        assert!(TokenAccount::unpack(&data).is_err());
+        data[108] = 1;
+        data[129] = 2;
+        assert!(TokenAccount::unpack(&data).is_err());
+    }
+
+    #[test]
+    fn unpacks_mint() {
+        let mut data = [0u8; MINT_LEN];
+        data[0] = 1;
+        data[4..36].fill(4);
+        data[36..44].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[44] = 6;
+        data[45] = 1;
+
+        let mint = Mint::unpack(&data).unwrap();
+        assert_eq!(mint.mint_authority, Some(Address::new([4; 32])));
+        assert_eq!(mint.supply, 1_000_000);
+        assert_eq!(mint.decimals, 6);
+        assert!(mint.is_initialized);
+        assert_eq!(mint.freeze_authority, None);
+        assert_eq!(mint.token_amount(1_500_000).to_string(), "1.5");
+
+        assert!(Mint::unpack(&data[..MINT_LEN - 1]).is_err());
+    }
+}
@@ -1,4 +1,17 @@
-use crate::{crypto::Keypair, hash::Hash, prelude::Instruction};
+use crate::{
+    crypto::{Address, Keypair},
+    hash::Hash,
+    prelude::Instruction,
+    signature::Signature,
+    types::{Result, SdkError},
+};
+
+/// Largest serialized transaction the cluster accepts.
+pub const PACKET_DATA_SIZE: usize = 1232;
+/// Largest compiled message this SDK builds.
+pub const MAX_COMPILED_MESSAGE_LEN: usize = 1200;
+/// Most distinct accounts a compiled message may reference.
+pub const MAX_ACCOUNTS: usize = 35;
 
 pub struct Transaction<'a, 'b, 'c, 'd, 'e, 'f, 'g, 'h>
 where
@@ -12,3 +25,511 @@ where
     pub instructions: &'c [Instruction<'d, 'e, 'f, 'g>],
     pub recent_blockhash: &'h Hash,
 }
+
+impl Transaction<'_, '_, '_, '_, '_, '_, '_, '_> {
+    /// Compiles the legacy message that the signers sign.
+    pub fn compile_message(&self) -> Result<heapless::Vec<u8, MAX_COMPILED_MESSAGE_LEN>> {
+        struct KeyMetadata {
+            pub is_signer: bool,
+            pub is_writable: bool,
+        }
+
+        // get map of all accounts
+        let mut keys_meta_map: heapless::LinearMap<&Address, KeyMetadata, MAX_ACCOUNTS> =
+            heapless::LinearMap::new();
+        for instruction in self.instructions.iter() {
+            // program cannot be writable or signer so it is safe to overwrite
+            keys_meta_map
+                .insert(
+                    instruction.program_id,
+                    KeyMetadata {
+                        is_signer: false,
+                        is_writable: false,
+                    },
+                )
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+            for account_meta in instruction.accounts.iter() {
+                let key_meta = keys_meta_map.get_mut(account_meta.address);
+                if let Some(key_meta) = key_meta {
+                    key_meta.is_writable |= account_meta.is_writable;
+                    key_meta.is_signer |= account_meta.is_signer;
+                    continue;
+                }
+                keys_meta_map
+                    .insert(
+                        account_meta.address,
+                        KeyMetadata {
+                            is_signer: account_meta.is_signer,
+                            is_writable: account_meta.is_writable,
+                        },
+                    )
+                    .map_err(|_| SdkError::TransactionTooLarge)?;
+            }
+        }
+
+        let mut writable_signer_keys: heapless::Vec<&Address, MAX_ACCOUNTS> = heapless::Vec::new();
+        let mut readonly_signer_keys: heapless::Vec<&Address, MAX_ACCOUNTS> = heapless::Vec::new();
+        let mut writable_non_signer_keys: heapless::Vec<&Address, MAX_ACCOUNTS> =
+            heapless::Vec::new();
+        let mut readonly_non_signer_keys: heapless::Vec<&Address, MAX_ACCOUNTS> =
+            heapless::Vec::new();
+        let mut static_account_keys: heapless::Vec<&Address, MAX_ACCOUNTS> = heapless::Vec::new();
+
+        // the map holds at most MAX_ACCOUNTS keys, so every push fits
+        for (key, meta) in keys_meta_map.iter() {
+            let _ = match (meta.is_writable, meta.is_signer) {
+                (true, true) => writable_signer_keys.push(*key),
+                (true, false) => writable_non_signer_keys.push(*key),
+                (false, true) => readonly_signer_keys.push(*key),
+                (false, false) => readonly_non_signer_keys.push(*key),
+            };
+        }
+        let num_required_signatures: u8 =
+            (writable_signer_keys.len() + readonly_signer_keys.len()) as u8;
+        let num_readonly_signed_accounts: u8 = readonly_signer_keys.len() as u8;
+        let num_readonly_unsigned_accounts: u8 = readonly_non_signer_keys.len() as u8;
+        static_account_keys.extend(writable_signer_keys);
+        static_account_keys.extend(readonly_signer_keys);
+        static_account_keys.extend(writable_non_signer_keys);
+        static_account_keys.extend(readonly_non_signer_keys);
+
+        // build message
+        let mut msg_buffer: heapless::Vec<u8, MAX_COMPILED_MESSAGE_LEN> = heapless::Vec::new();
+
+        // SAFETY: msg_buffer is empty and has enough space
+        unsafe {
+            msg_buffer.push_unchecked(num_required_signatures);
+            msg_buffer.push_unchecked(num_readonly_signed_accounts);
+            msg_buffer.push_unchecked(num_readonly_unsigned_accounts);
+            // number of accounts is less then 128, so 1 byte is enough
+            msg_buffer.push_unchecked(static_account_keys.len() as u8);
+        };
+
+        for key in static_account_keys.iter() {
+            msg_buffer
+                .extend_from_slice(key.as_ref())
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+        }
+
+        msg_buffer
+            .extend_from_slice(self.recent_blockhash.as_ref())
+            .map_err(|_| SdkError::TransactionTooLarge)?;
+
+        // number of instructions is less then 128, so 1 byte is enough
+        msg_buffer
+            .push(self.instructions.len() as u8)
+            .map_err(|_| SdkError::TransactionTooLarge)?;
+
+        for instruction in self.instructions.iter() {
+            // find position of program_id in static_account_keys
+            let position = static_account_keys
+                .iter()
+                .position(|k| k == &instruction.program_id)
+                .unwrap(); // always exists
+
+            msg_buffer
+                .push(position as u8)
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+
+            // number of accounts is less then 128, so 1 byte is enough
+            msg_buffer
+                .push(instruction.accounts.len() as u8)
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+
+            for account_meta in instruction.accounts.iter() {
+                let key = account_meta.address;
+                // find position in static_account_keys
+                let position = static_account_keys.iter().position(|k| k == &key).unwrap(); // always exists
+
+                msg_buffer
+                    .push(position as u8)
+                    .map_err(|_| SdkError::TransactionTooLarge)?;
+            }
+
+            // size of data as compact-u16
+            let data_len = instruction.data.len();
+            if data_len < 128 {
+                msg_buffer
+                    .push(data_len as u8)
+                    .map_err(|_| SdkError::TransactionTooLarge)?;
+            } else {
+                msg_buffer
+                    .extend_from_slice(&[(data_len as u8 & 0x7f) | 0x80, (data_len >> 7) as u8])
+                    .map_err(|_| SdkError::TransactionTooLarge)?;
+            }
+            msg_buffer
+                .extend_from_slice(instruction.data)
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+        }
+
+        Ok(msg_buffer)
+    }
+
+    /// Distinct accounts any instruction writes to, in first-use order.
+    pub fn writable_accounts(&self) -> heapless::Vec<&Address, MAX_ACCOUNTS> {
+        let mut writable: heapless::Vec<&Address, MAX_ACCOUNTS> = heapless::Vec::new();
+        let accounts = self.instructions.iter().flat_map(|i| i.accounts.iter());
+        for account in accounts.filter(|account| account.is_writable) {
+            if !writable.contains(&account.address) {
+                // a message cannot reference more than MAX_ACCOUNTS keys
+                let _ = writable.push(account.address);
+            }
+        }
+        writable
+    }
+
+    /// Signs the compiled message and returns the wire-format transaction.
+    pub fn serialize(&self) -> Result<heapless::Vec<u8, PACKET_DATA_SIZE>> {
+        let message = self.compile_message()?;
+        let mut transaction_bytes: heapless::Vec<u8, PACKET_DATA_SIZE> = heapless::Vec::new();
+
+        // SAFETY: transaction_bytes is empty
+        unsafe {
+            transaction_bytes.push_unchecked(self.signers.len() as u8);
+        }
+
+        for signer in self.signers.iter() {
+            let signature = signer.sign_message(message.as_slice(), None);
+            transaction_bytes
+                .extend_from_slice(signature.as_ref())
+                .map_err(|_| SdkError::TransactionTooLarge)?;
+        }
+
+        transaction_bytes
+            .extend_from_slice(message.as_slice())
+            .map_err(|_| SdkError::TransactionTooLarge)?;
+        Ok(transaction_bytes)
+    }
+}
+
+/// Read-only view of a compiled legacy or v0 message.
+#[derive(Debug, Clone, Copy)]
+pub struct CompiledMessage<'a> {
+    /// `None` for legacy messages.
+    pub version: Option<u8>,
+    pub num_required_signatures: u8,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+    account_keys: &'a [u8],
+    pub recent_blockhash: Hash,
+    instructions: &'a [u8],
+    num_instructions: u16,
+}
+
+/// Instruction inside a `CompiledMessage`, referencing accounts by index.
+#[derive(Debug, Clone, Copy)]
+pub struct CompiledInstruction<'a> {
+    pub program_id_index: u8,
+    pub accounts: &'a [u8],
+    pub data: &'a [u8],
+}
+
+impl<'a> CompiledMessage<'a> {
+    /// Parses a legacy or v0 message. Accounts a v0 message loads from
+    /// lookup tables are not resolved; `account_key` returns `None` for them.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let version = match bytes.first() {
+            Some(&prefix) if prefix & 0x80 != 0 => {
+                reader.take(1)?;
+                match prefix & 0x7f {
+                    0 => Some(0),
+                    _ => return Err(SdkError::Unsupported),
+                }
+            }
+            _ => None,
+        };
+        let header = reader.take(3)?;
+        let num_keys = reader.compact_u16()? as usize;
+        let account_keys = reader.take(num_keys * 32)?;
+        let mut recent_blockhash = [0u8; 32];
+        recent_blockhash.copy_from_slice(reader.take(32)?);
+        let num_instructions = reader.compact_u16()?;
+        let instructions = &bytes[reader.pos..];
+        let message = CompiledMessage {
+            version,
+            num_required_signatures: header[0],
+            num_readonly_signed_accounts: header[1],
+            num_readonly_unsigned_accounts: header[2],
+            account_keys,
+            recent_blockhash: Hash::from(recent_blockhash),
+            instructions,
+            num_instructions,
+        };
+        // validate the instruction section up front so iteration cannot fail
+        let mut reader = Reader {
+            bytes: instructions,
+            pos: 0,
+        };
+        let mut highest_account = None;
+        for _ in 0..num_instructions {
+            let instruction = reader.instruction()?;
+            if instruction.program_id_index as usize >= num_keys {
+                return Err(SdkError::Deserialize);
+            }
+            highest_account = highest_account.max(instruction.accounts.iter().max().copied());
+        }
+        // v0 messages end with the lookups loading the remaining accounts
+        let mut num_accounts = num_keys;
+        if version.is_some() {
+            for _ in 0..reader.compact_u16()? {
+                reader.take(32)?;
+                for _ in 0..2 {
+                    let len = reader.compact_u16()? as usize;
+                    reader.take(len)?;
+                    num_accounts += len;
+                }
+            }
+        }
+        if highest_account.is_some_and(|index| index as usize >= num_accounts) {
+            return Err(SdkError::Deserialize);
+        }
+        Ok(message)
+    }
+
+    /// The first account key, which pays the fee.
+    pub fn fee_payer(&self) -> Option<&'a Address> {
+        self.account_key(0)
+    }
+
+    pub fn account_key(&self, index: usize) -> Option<&'a Address> {
+        self.account_keys
+            .get(index * 32..index * 32 + 32)
+            .map(|key| <&[u8; 32]>::try_from(key).unwrap().as_ref())
+    }
+
+    pub fn account_keys(&self) -> impl Iterator<Item = &'a Address> {
+        self.account_keys
+            .chunks_exact(32)
+            .map(|key| <&[u8; 32]>::try_from(key).unwrap().as_ref())
+    }
+
+    pub fn instructions(&self) -> impl Iterator<Item = CompiledInstruction<'a>> {
+        let mut reader = Reader {
+            bytes: self.instructions,
+            pos: 0,
+        };
+        // validated in `parse`
+        (0..self.num_instructions).map_while(move |_| reader.instruction().ok())
+    }
+}
+
+/// Read-only view of a signed wire-format transaction.
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionView<'a> {
+    signatures: &'a [u8],
+    pub message: CompiledMessage<'a>,
+    message_bytes: &'a [u8],
+}
+
+impl<'a> TransactionView<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let num_signatures = reader.compact_u16()? as usize;
+        let signatures = reader.take(num_signatures * 64)?;
+        let message_bytes = &bytes[reader.pos..];
+        Ok(TransactionView {
+            signatures,
+            message: CompiledMessage::parse(message_bytes)?,
+            message_bytes,
+        })
+    }
+
+    pub fn signatures(&self) -> impl Iterator<Item = Signature> + 'a {
+        self.signatures
+            .chunks_exact(64)
+            .map(|signature| Signature::from(<[u8; 64]>::try_from(signature).unwrap()))
+    }
+
+    /// Serialized message the signatures cover.
+    pub fn message_bytes(&self) -> &'a [u8] {
+        self.message_bytes
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + len)
+            .ok_or(SdkError::Deserialize)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn compact_u16(&mut self) -> Result<u16> {
+        let mut value = 0u32;
+        for i in 0..3 {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as u32) << (7 * i);
+            if byte & 0x80 == 0 {
+                return u16::try_from(value).map_err(|_| SdkError::Deserialize);
+            }
+        }
+        Err(SdkError::Deserialize)
+    }
+
+    fn instruction(&mut self) -> Result<CompiledInstruction<'a>> {
+        let program_id_index = self.take(1)?[0];
+        let num_accounts = self.compact_u16()? as usize;
+        let accounts = self.take(num_accounts)?;
+        let data_len = self.compact_u16()? as usize;
+        let data = self.take(data_len)?;
+        Ok(CompiledInstruction {
+            program_id_index,
+            accounts,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::AccountMeta;
+
+    const PROGRAM: Address = Address::new([9; 32]);
+
+    /// Version prefix, header, keys, blockhash, one instruction using a
+    /// loaded account, then `lookups` lookup tables of one writable and one
+    /// readonly account each.
+    fn v0_message(lookups: u8) -> heapless::Vec<u8, 512> {
+        let mut message = heapless::Vec::new();
+        message.extend_from_slice(&[0x80, 1, 0, 1, 2]).unwrap();
+        message.extend_from_slice(&[1; 32]).unwrap();
+        message.extend_from_slice(PROGRAM.as_ref()).unwrap();
+        message.extend_from_slice(&[7; 32]).unwrap();
+        // program 1, accounts [0, 2], data [5]
+        message.extend_from_slice(&[1, 1, 2, 0, 2, 1, 5]).unwrap();
+        message.push(lookups).unwrap();
+        for table in 0..lookups {
+            message.extend_from_slice(&[20 + table; 32]).unwrap();
+            message.extend_from_slice(&[1, 3, 1, 4]).unwrap();
+        }
+        message
+    }
+
+    #[test]
+    fn parses_legacy_transactions() {
+        let payer = Keypair::new_from_seed([1; 32]);
+        let signer = Keypair::new_from_seed([2; 32]);
+        let readonly = Address::new([3; 32]);
+        let metas = [
+            AccountMeta::new_writable(payer.public_key(), true),
+            AccountMeta::new_writable(signer.public_key(), true),
+            AccountMeta::new_readonly(&readonly, false),
+        ];
+        let instructions = [Instruction {
+            program_id: &PROGRAM,
+            data: &[1, 2, 3],
+            accounts: &metas,
+        }];
+        let blockhash = Hash::from([7; 32]);
+        let transaction = Transaction {
+            signers: &[&payer, &signer],
+            instructions: &instructions,
+            recent_blockhash: &blockhash,
+        };
+        let bytes = transaction.serialize().unwrap();
+        let view = TransactionView::parse(&bytes).unwrap();
+
+        let message = transaction.compile_message().unwrap();
+        assert_eq!(view.message_bytes(), message.as_slice());
+        let signatures: heapless::Vec<Signature, 2> = view.signatures().collect();
+        assert_eq!(
+            signatures,
+            [
+                Signature::from(payer.sign_message(&message, None)),
+                Signature::from(signer.sign_message(&message, None)),
+            ]
+        );
+
+        let message = view.message;
+        assert_eq!(message.version, None);
+        assert_eq!(message.num_required_signatures, 2);
+        assert_eq!(message.num_readonly_signed_accounts, 0);
+        assert_eq!(message.num_readonly_unsigned_accounts, 2);
+        assert_eq!(message.recent_blockhash, blockhash);
+        assert_eq!(message.fee_payer(), Some(payer.public_key()));
+        assert_eq!(message.account_keys().count(), 4);
+        assert_eq!(message.account_key(4), None);
+        let instruction = message.instructions().next().unwrap();
+        assert_eq!(
+            message.account_key(instruction.program_id_index as usize),
+            Some(&PROGRAM)
+        );
+        let accounts: heapless::Vec<&Address, 3> = instruction
+            .accounts
+            .iter()
+            .map(|&index| message.account_key(index as usize).unwrap())
+            .collect();
+        assert_eq!(
+            accounts,
+            [payer.public_key(), signer.public_key(), &readonly]
+        );
+        assert_eq!(instruction.data, [1, 2, 3]);
+        assert_eq!(message.instructions().count(), 1);
+    }
+
+    #[test]
+    fn parses_v0_messages_with_lookups() {
+        let bytes = v0_message(1);
+        let message = CompiledMessage::parse(&bytes).unwrap();
+        assert_eq!(message.version, Some(0));
+        assert_eq!(message.num_readonly_unsigned_accounts, 1);
+        assert_eq!(message.account_keys().count(), 2);
+        assert_eq!(message.recent_blockhash, Hash::from([7; 32]));
+        let instruction = message.instructions().next().unwrap();
+        assert_eq!(instruction.accounts, [0, 2]);
+        assert_eq!(instruction.data, [5]);
+        // index 2 is loaded from the table and not resolved
+        assert_eq!(message.account_key(2), None);
+        assert!(CompiledMessage::parse(&v0_message(2)).is_ok());
+
+        // without a table the instruction references a missing account
+        assert!(matches!(
+            CompiledMessage::parse(&v0_message(0)),
+            Err(SdkError::Deserialize)
+        ));
+        let mut bytes = v0_message(1);
+        bytes[0] = 0x81;
+        assert!(matches!(
+            CompiledMessage::parse(&bytes),
+            Err(SdkError::Unsupported)
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let payer = Keypair::new_from_seed([1; 32]);
+        let metas = [AccountMeta::new_writable(payer.public_key(), true)];
+        let instructions = [Instruction {
+            program_id: &PROGRAM,
+            data: &[1, 2, 3],
+            accounts: &metas,
+        }];
+        let transaction = Transaction {
+            signers: &[&payer],
+            instructions: &instructions,
+            recent_blockhash: &Hash::from([7; 32]),
+        };
+        let bytes = transaction.serialize().unwrap();
+        for len in 0..bytes.len() {
+            assert!(TransactionView::parse(&bytes[..len]).is_err(), "{len}");
+        }
+        let bytes = v0_message(2);
+        for len in 0..bytes.len() {
+            assert!(CompiledMessage::parse(&bytes[..len]).is_err(), "{len}");
+        }
+
+        // a compact-u16 longer than three bytes
+        assert!(TransactionView::parse(&[0x80, 0x80, 0x80, 0x01]).is_err());
+        // 0x7f signatures with none present
+        assert!(TransactionView::parse(&[0x7f]).is_err());
+    }
+}
@@ -0,0 +1,383 @@
+use crate::{
+    crypto::Address,
+    instruction::{SYSTEM_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+    json::{Decode, Value},
+    types::{Result, SdkError},
+};
+
+/// Defines an error enum whose unit variants are serialized by name, plus
+/// the given data-carrying variants, with `name()` and `from_name()`.
+macro_rules! named_errors {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($unit:ident,)*
+            ;
+            $($(#[$data_meta:meta])* $data:ident $body:tt,)*
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $($unit,)*
+            $($(#[$data_meta])* $data $body,)*
+            /// A variant this SDK does not know about yet.
+            Unknown,
+        }
+
+        impl $name {
+            fn from_name(name: &str) -> Self {
+                match name {
+                    $(stringify!($unit) => $name::$unit,)*
+                    _ => $name::Unknown,
+                }
+            }
+
+            /// Runtime name of the variant.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $($name::$unit => stringify!($unit),)*
+                    $($name::$data { .. } => stringify!($data),)*
+                    $name::Unknown => "Unknown",
+                }
+            }
+        }
+    };
+}
+
+named_errors! {
+    /// Why a transaction failed, mirroring the runtime's `TransactionError`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TransactionError {
+        AccountInUse,
+        AccountLoadedTwice,
+        AccountNotFound,
+        ProgramAccountNotFound,
+        InsufficientFundsForFee,
+        InvalidAccountForFee,
+        AlreadyProcessed,
+        BlockhashNotFound,
+        CallChainTooDeep,
+        MissingSignatureForFee,
+        InvalidAccountIndex,
+        SignatureFailure,
+        InvalidProgramForExecution,
+        SanitizeFailure,
+        ClusterMaintenance,
+        AccountBorrowOutstanding,
+        WouldExceedMaxBlockCostLimit,
+        UnsupportedVersion,
+        InvalidWritableAccount,
+        WouldExceedMaxAccountCostLimit,
+        WouldExceedAccountDataBlockLimit,
+        TooManyAccountLocks,
+        AddressLookupTableNotFound,
+        InvalidAddressLookupTableOwner,
+        InvalidAddressLookupTableData,
+        InvalidAddressLookupTableIndex,
+        InvalidRentPayingAccount,
+        WouldExceedMaxVoteCostLimit,
+        WouldExceedAccountDataTotalLimit,
+        MaxLoadedAccountsDataSizeExceeded,
+        InvalidLoadedAccountsDataSizeLimit,
+        ResanitizationNeeded,
+        UnbalancedTransaction,
+        ProgramCacheHitMaxLimit,
+        CommitCancelled,
+        ;
+        /// Instruction at the given index failed.
+        InstructionError(u8, InstructionError),
+        DuplicateInstruction(u8),
+        InsufficientFundsForRent { account_index: u8 },
+        ProgramExecutionTemporarilyRestricted { account_index: u8 },
+    }
+}
+
+named_errors! {
+    /// Why an instruction failed, mirroring the runtime's `InstructionError`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum InstructionError {
+        GenericError,
+        InvalidArgument,
+        InvalidInstructionData,
+        InvalidAccountData,
+        AccountDataTooSmall,
+        InsufficientFunds,
+        IncorrectProgramId,
+        MissingRequiredSignature,
+        AccountAlreadyInitialized,
+        UninitializedAccount,
+        UnbalancedInstruction,
+        ModifiedProgramId,
+        ExternalAccountLamportSpend,
+        ExternalAccountDataModified,
+        ReadonlyLamportChange,
+        ReadonlyDataModified,
+        DuplicateAccountIndex,
+        ExecutableModified,
+        RentEpochModified,
+        NotEnoughAccountKeys,
+        AccountDataSizeChanged,
+        AccountNotExecutable,
+        AccountBorrowFailed,
+        AccountBorrowOutstanding,
+        DuplicateAccountOutOfSync,
+        InvalidError,
+        ExecutableDataModified,
+        ExecutableLamportChange,
+        ExecutableAccountNotRentExempt,
+        UnsupportedProgramId,
+        CallDepth,
+        MissingAccount,
+        ReentrancyNotAllowed,
+        MaxSeedLengthExceeded,
+        InvalidSeeds,
+        InvalidRealloc,
+        ComputationalBudgetExceeded,
+        PrivilegeEscalation,
+        ProgramEnvironmentSetupFailure,
+        ProgramFailedToComplete,
+        ProgramFailedToCompile,
+        Immutable,
+        IncorrectAuthority,
+        BorshIoError,
+        AccountNotRentExempt,
+        InvalidAccountOwner,
+        ArithmeticOverflow,
+        UnsupportedSysvar,
+        IllegalOwner,
+        MaxAccountsDataAllocationsExceeded,
+        MaxAccountsExceeded,
+        MaxInstructionTraceLengthExceeded,
+        BuiltinProgramsMustConsumeComputeUnits,
+        ;
+        /// Program-specific error code.
+        Custom(u32),
+    }
+}
+
+impl TransactionError {
+    /// The failing instruction's index and error, if an instruction failed.
+    pub fn instruction_error(&self) -> Option<(u8, InstructionError)> {
+        match self {
+            TransactionError::InstructionError(index, error) => Some((*index, *error)),
+            _ => None,
+        }
+    }
+}
+
+impl InstructionError {
+    /// Readable name of a `Custom` code raised by `program_id`, for the
+    /// System and SPL Token programs.
+    pub fn custom_error_name(&self, program_id: &Address) -> Option<&'static str> {
+        match self {
+            InstructionError::Custom(code) => custom_error_name(program_id, *code),
+            _ => None,
+        }
+    }
+}
+
+/// Readable name of custom error `code` raised by `program_id`.
+pub fn custom_error_name(program_id: &Address, code: u32) -> Option<&'static str> {
+    const SYSTEM_ERRORS: [&str; 9] = [
+        "AccountAlreadyInUse",
+        "ResultWithNegativeLamports",
+        "InvalidProgramId",
+        "InvalidAccountDataLength",
+        "MaxSeedLengthExceeded",
+        "AddressWithSeedMismatch",
+        "NonceNoRecentBlockhashes",
+        "NonceBlockhashNotExpired",
+        "NonceUnexpectedBlockhashValue",
+    ];
+    const TOKEN_ERRORS: [&str; 20] = [
+        "NotRentExempt",
+        "InsufficientFunds",
+        "InvalidMint",
+        "MintMismatch",
+        "OwnerMismatch",
+        "FixedSupply",
+        "AlreadyInUse",
+        "InvalidNumberOfProvidedSigners",
+        "InvalidNumberOfRequiredSigners",
+        "UninitializedState",
+        "NativeNotSupported",
+        "NonNativeHasBalance",
+        "InvalidInstruction",
+        "InvalidState",
+        "Overflow",
+        "AuthorityTypeNotSupported",
+        "MintCannotFreeze",
+        "AccountFrozen",
+        "MintDecimalsMismatch",
+        "NonNativeNotSupported",
+    ];
+    let names: &[&str] = if program_id == &SYSTEM_PROGRAM_ID {
+        &SYSTEM_ERRORS
+    } else if program_id == &TOKEN_PROGRAM_ID || program_id == &TOKEN_2022_PROGRAM_ID {
+        &TOKEN_ERRORS
+    } else {
+        return None;
+    };
+    names.get(code as usize).copied()
+}
+
+/// Unit variants are strings; the rest are single-member objects, e.g.
+/// `{"InstructionError":[0,{"Custom":1}]}`.
+fn variant<'a>(value: Value<'a>) -> Result<(&'a str, Option<Value<'a>>)> {
+    if let Some(name) = value.as_str() {
+        return Ok((name.as_str().ok_or(SdkError::ResponseParseError)?, None));
+    }
+    let (name, inner) = value.members().next().ok_or(SdkError::ResponseParseError)?;
+    Ok((
+        name.as_str().ok_or(SdkError::ResponseParseError)?,
+        Some(inner),
+    ))
+}
+
+impl Decode<'_> for TransactionError {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let (name, inner) = variant(value)?;
+        let Some(inner) = inner else {
+            return Ok(TransactionError::from_name(name));
+        };
+        Ok(match name {
+            "InstructionError" => TransactionError::InstructionError(
+                inner.at(0).ok_or(SdkError::ResponseParseError)?.decode()?,
+                inner.at(1).ok_or(SdkError::ResponseParseError)?.decode()?,
+            ),
+            "DuplicateInstruction" => TransactionError::DuplicateInstruction(inner.decode()?),
+            "InsufficientFundsForRent" => TransactionError::InsufficientFundsForRent {
+                account_index: inner.field("account_index")?,
+            },
+            "ProgramExecutionTemporarilyRestricted" => {
+                TransactionError::ProgramExecutionTemporarilyRestricted {
+                    account_index: inner.field("account_index")?,
+                }
+            }
+            _ => TransactionError::Unknown,
+        })
+    }
+}
+
+impl Decode<'_> for InstructionError {
+    fn decode(value: Value<'_>) -> Result<Self> {
+        let (name, inner) = variant(value)?;
+        Ok(match (name, inner) {
+            ("Custom", Some(code)) => InstructionError::Custom(code.decode()?),
+            // older nodes send `{"BorshIoError":"message"}`
+            (name, _) => InstructionError::from_name(name),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(json: &[u8]) -> Result<TransactionError> {
+        Value::parse(json)?.decode()
+    }
+
+    #[test]
+    fn decodes_instruction_errors() {
+        let error = decode(br#"{"InstructionError":[0,{"Custom":1}]}"#).unwrap();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(0, InstructionError::Custom(1))
+        );
+        assert_eq!(
+            error.instruction_error(),
+            Some((0, InstructionError::Custom(1)))
+        );
+        assert_eq!(error.name(), "InstructionError");
+
+        let error = decode(br#"{"InstructionError":[2,"InvalidAccountData"]}"#).unwrap();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(2, InstructionError::InvalidAccountData)
+        );
+        let error = decode(br#"{"InstructionError":[1,{"BorshIoError":"eof"}]}"#).unwrap();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(1, InstructionError::BorshIoError)
+        );
+        assert!(decode(br#"{"InstructionError":[0]}"#).is_err());
+    }
+
+    #[test]
+    fn decodes_variants_by_name() {
+        assert_eq!(
+            decode(br#""BlockhashNotFound""#).unwrap(),
+            TransactionError::BlockhashNotFound
+        );
+        assert_eq!(
+            decode(br#"{"InsufficientFundsForRent":{"account_index":3}}"#).unwrap(),
+            TransactionError::InsufficientFundsForRent { account_index: 3 }
+        );
+        // without the index there is nothing to report for the account
+        assert_eq!(
+            decode(br#""InsufficientFundsForRent""#).unwrap(),
+            TransactionError::Unknown
+        );
+        assert!(decode(br#"{"InsufficientFundsForRent":{}}"#).is_err());
+        assert_eq!(
+            decode(br#"{"DuplicateInstruction":4}"#).unwrap(),
+            TransactionError::DuplicateInstruction(4)
+        );
+        assert_eq!(TransactionError::AccountInUse.name(), "AccountInUse");
+        assert_eq!(
+            TransactionError::InsufficientFundsForRent { account_index: 0 }.name(),
+            "InsufficientFundsForRent"
+        );
+    }
+
+    #[test]
+    fn decodes_unknown_names() {
+        assert_eq!(
+            decode(br#""SomeFutureError""#).unwrap(),
+            TransactionError::Unknown
+        );
+        assert_eq!(
+            decode(br#"{"SomeFutureError":[1,2]}"#).unwrap(),
+            TransactionError::Unknown
+        );
+        assert_eq!(
+            decode(br#"{"InstructionError":[0,"SomeFutureError"]}"#).unwrap(),
+            TransactionError::InstructionError(0, InstructionError::Unknown)
+        );
+        assert_eq!(TransactionError::Unknown.name(), "Unknown");
+        assert!(decode(b"42").is_err());
+    }
+
+    #[test]
+    fn names_custom_errors() {
+        assert_eq!(
+            custom_error_name(&SYSTEM_PROGRAM_ID, 0),
+            Some("AccountAlreadyInUse")
+        );
+        assert_eq!(
+            custom_error_name(&SYSTEM_PROGRAM_ID, 1),
+            Some("ResultWithNegativeLamports")
+        );
+        assert_eq!(custom_error_name(&SYSTEM_PROGRAM_ID, 9), None);
+        assert_eq!(
+            custom_error_name(&TOKEN_PROGRAM_ID, 1),
+            Some("InsufficientFunds")
+        );
+        assert_eq!(
+            custom_error_name(&TOKEN_2022_PROGRAM_ID, 17),
+            Some("AccountFrozen")
+        );
+        assert_eq!(custom_error_name(&TOKEN_PROGRAM_ID, 20), None);
+        assert_eq!(custom_error_name(&Address::new([9; 32]), 0), None);
+
+        assert_eq!(
+            InstructionError::Custom(1).custom_error_name(&TOKEN_PROGRAM_ID),
+            Some("InsufficientFunds")
+        );
+        assert_eq!(
+            InstructionError::InvalidArgument.custom_error_name(&TOKEN_PROGRAM_ID),
+            None
+        );
+    }
+}
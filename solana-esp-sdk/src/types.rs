@@ -1,3 +1,5 @@
+use crate::{hash::Hash, transaction_error::TransactionError};
+
 /// SDK-wide error type. Keep tiny; map externals into this.
 #[derive(Debug)]
 pub enum SdkError {
@@ -11,6 +13,92 @@ pub enum SdkError {
     NetworkError,
     ResponseParseError,
     TransactionTooLarge,
+    Storage,
+    /// The node answered with a JSON-RPC `error` object.
+    RpcError(RpcError),
+    /// A simulated transaction failed.
+    TransactionFailed(TransactionError),
+    /// The endpoint belongs to another cluster; holds the genesis hash it
+    /// reported.
+    WrongCluster(Hash),
 }
 
 pub type Result<T> = core::result::Result<T, SdkError>;
+
+/// Bytes of the node's error message kept in `RpcError`.
+pub const MAX_RPC_ERROR_MESSAGE_LEN: usize = 64;
+
+/// JSON-RPC error returned by the node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub kind: RpcErrorKind,
+    /// Start of the node's message, truncated to `MAX_RPC_ERROR_MESSAGE_LEN`.
+    pub message: heapless::String<MAX_RPC_ERROR_MESSAGE_LEN>,
+    /// `data.err` of a failed preflight simulation.
+    pub transaction_error: Option<TransactionError>,
+}
+
+/// Well-known JSON-RPC and Solana server error codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcErrorKind {
+    /// Preflight failed because the transaction's blockhash is unknown or expired.
+    BlockhashNotFound,
+    BlockCleanedUp,
+    SendTransactionPreflightFailure,
+    TransactionSignatureVerificationFailure,
+    BlockNotAvailable,
+    NodeUnhealthy,
+    TransactionPrecompileVerificationFailure,
+    SlotSkipped,
+    NoSnapshot,
+    LongTermStorageSlotSkipped,
+    KeyExcludedFromSecondaryIndex,
+    TransactionHistoryNotAvailable,
+    ScanError,
+    TransactionSignatureLenMismatch,
+    BlockStatusNotAvailableYet,
+    UnsupportedTransactionVersion,
+    MinContextSlotNotReached,
+    EpochRewardsPeriodActive,
+    SlotNotEpochBoundary,
+    LongTermStorageUnreachable,
+    ParseError,
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    Other,
+}
+
+impl RpcErrorKind {
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            -32001 => RpcErrorKind::BlockCleanedUp,
+            -32002 => RpcErrorKind::SendTransactionPreflightFailure,
+            -32003 => RpcErrorKind::TransactionSignatureVerificationFailure,
+            -32004 => RpcErrorKind::BlockNotAvailable,
+            -32005 => RpcErrorKind::NodeUnhealthy,
+            -32006 => RpcErrorKind::TransactionPrecompileVerificationFailure,
+            -32007 => RpcErrorKind::SlotSkipped,
+            -32008 => RpcErrorKind::NoSnapshot,
+            -32009 => RpcErrorKind::LongTermStorageSlotSkipped,
+            -32010 => RpcErrorKind::KeyExcludedFromSecondaryIndex,
+            -32011 => RpcErrorKind::TransactionHistoryNotAvailable,
+            -32012 => RpcErrorKind::ScanError,
+            -32013 => RpcErrorKind::TransactionSignatureLenMismatch,
+            -32014 => RpcErrorKind::BlockStatusNotAvailableYet,
+            -32015 => RpcErrorKind::UnsupportedTransactionVersion,
+            -32016 => RpcErrorKind::MinContextSlotNotReached,
+            -32017 => RpcErrorKind::EpochRewardsPeriodActive,
+            -32018 => RpcErrorKind::SlotNotEpochBoundary,
+            -32019 => RpcErrorKind::LongTermStorageUnreachable,
+            -32700 => RpcErrorKind::ParseError,
+            -32600 => RpcErrorKind::InvalidRequest,
+            -32601 => RpcErrorKind::MethodNotFound,
+            -32602 => RpcErrorKind::InvalidParams,
+            -32603 => RpcErrorKind::InternalError,
+            _ => RpcErrorKind::Other,
+        }
+    }
+}